@@ -1,13 +1,25 @@
-use std::sync::{
-    atomic::{self, AtomicBool},
-    Arc,
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
 };
 
 use http::HeaderMap;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tokio::sync::broadcast;
 
-use crate::model::types::{graphql_endpoints::GraphQLEndpoints, message::Message};
+use crate::{
+    breakpoints::Breakpoints,
+    message_sender::MessageSender,
+    model::types::{
+        config_changed_event::ConfigChangedEvent, connection_init_rule::ConnectionInitRule,
+        fault_rule::FaultRule, graphql_endpoints::GraphQLEndpoints, message::Message,
+        operation_stub::OperationStub, upstream_profile::UpstreamProfile,
+    },
+    ws_connections::WsConnections,
+};
 
 #[derive(Debug, Clone)]
 pub struct ConnectionId(Arc<String>);
@@ -23,13 +35,31 @@ impl ConnectionId {
 }
 
 struct AdminStateInner {
-    message_sender: broadcast::Sender<Message>,
+    message_sender: MessageSender,
     prohibit_mutation: AtomicBool,
     server_endpoints: RwLock<GraphQLEndpoints>,
     request_headers: Arc<RwLock<HeaderMap>>,
+    accept_invalid_certificates: AtomicBool,
     response_headers: Arc<RwLock<HeaderMap>>,
+    operation_stubs: RwLock<HashMap<String, OperationStub>>,
+    fault_rules: RwLock<Vec<FaultRule>>,
+    connection_init_rules: RwLock<Vec<ConnectionInitRule>>,
+    breakpoints: Breakpoints,
+    ws_connections: WsConnections,
+    config_changed_sender: broadcast::Sender<ConfigChangedEvent>,
+    upstream_profiles: RwLock<BTreeMap<String, UpstreamProfile>>,
+    active_upstream_profile: RwLock<Option<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpstreamSettings {
+    pub server_endpoints: GraphQLEndpoints,
+    pub request_headers: HeaderMap,
+    pub accept_invalid_certificates: bool,
 }
 
+const CONFIG_CHANGED_CHANNEL_CAPACITY: usize = 16;
+
 #[derive(Clone)]
 pub struct AdminState(Arc<AdminStateInner>);
 
@@ -40,20 +70,30 @@ impl AdminState {
         prohibit_mutation: bool,
         request_headers: HeaderMap,
         response_headers: HeaderMap,
+        message_history_size: usize,
     ) -> Self {
         Self(Arc::new(AdminStateInner {
-            message_sender: broadcast::channel(128).0,
+            message_sender: MessageSender::new(message_history_size),
             prohibit_mutation: AtomicBool::new(prohibit_mutation),
             server_endpoints: RwLock::new(GraphQLEndpoints {
                 graphql_endpoint: server_graphql_endpoint.into(),
                 graphql_ws_endpoint: server_graphql_ws_endpoint.into(),
             }),
             request_headers: Arc::new(RwLock::new(request_headers)),
+            accept_invalid_certificates: AtomicBool::new(false),
             response_headers: Arc::new(RwLock::new(response_headers)),
+            operation_stubs: RwLock::new(HashMap::new()),
+            fault_rules: RwLock::new(Vec::new()),
+            connection_init_rules: RwLock::new(Vec::new()),
+            breakpoints: Breakpoints::new(),
+            ws_connections: WsConnections::new(),
+            config_changed_sender: broadcast::Sender::new(CONFIG_CHANGED_CHANNEL_CAPACITY),
+            upstream_profiles: RwLock::new(BTreeMap::new()),
+            active_upstream_profile: RwLock::new(None),
         }))
     }
 
-    pub fn message_sender_ref(&self) -> &broadcast::Sender<Message> {
+    pub fn message_sender_ref(&self) -> &MessageSender {
         &self.0.message_sender
     }
 
@@ -86,4 +126,155 @@ impl AdminState {
     pub fn response_headers(&self) -> &Arc<RwLock<HeaderMap>> {
         &self.0.response_headers
     }
+
+    pub fn accept_invalid_certificates(&self) -> bool {
+        self.0
+            .accept_invalid_certificates
+            .load(atomic::Ordering::SeqCst)
+    }
+
+    pub fn set_accept_invalid_certificates(&self, accept_invalid_certificates: bool) -> bool {
+        self.0
+            .accept_invalid_certificates
+            .swap(accept_invalid_certificates, atomic::Ordering::SeqCst)
+    }
+
+    pub fn upstream_settings(&self) -> UpstreamSettings {
+        let server_endpoints = self.0.server_endpoints.read();
+        let request_headers = self.0.request_headers.read();
+
+        UpstreamSettings {
+            server_endpoints: server_endpoints.clone(),
+            request_headers: request_headers.clone(),
+            accept_invalid_certificates: self.accept_invalid_certificates(),
+        }
+    }
+
+    pub fn upstream_profiles(&self) -> &RwLock<BTreeMap<String, UpstreamProfile>> {
+        &self.0.upstream_profiles
+    }
+
+    pub fn active_upstream_profile(&self) -> &RwLock<Option<String>> {
+        &self.0.active_upstream_profile
+    }
+
+    // the server endpoints are always locked before the request headers, so a request never sees
+    // a half applied change
+    pub fn update_upstream_settings<T>(
+        &self,
+        update: impl FnOnce(&mut GraphQLEndpoints, &mut HeaderMap) -> T,
+    ) -> T {
+        let mut server_endpoints = self.0.server_endpoints.write();
+        let mut request_headers = self.0.request_headers.write();
+
+        update(&mut server_endpoints, &mut request_headers)
+    }
+
+    // the settings changed by hand no longer match the active upstream profile
+    pub fn override_upstream_settings<T>(
+        &self,
+        update: impl FnOnce(&mut GraphQLEndpoints, &mut HeaderMap) -> T,
+    ) -> T {
+        self.update_upstream_settings(|server_endpoints, request_headers| {
+            *self.0.active_upstream_profile.write() = None;
+            update(server_endpoints, request_headers)
+        })
+    }
+
+    pub fn activate_upstream_profile(&self, name: &str) -> Option<UpstreamProfile> {
+        let upstream_profile = self.0.upstream_profiles.read().get(name).cloned()?;
+
+        self.update_upstream_settings(|server_endpoints, request_headers| {
+            *server_endpoints = upstream_profile.server_endpoints.clone();
+            *request_headers = upstream_profile.request_headers.clone();
+            self.set_accept_invalid_certificates(upstream_profile.accept_invalid_certificates);
+            *self.0.active_upstream_profile.write() = Some(upstream_profile.name.clone());
+        });
+
+        Some(upstream_profile)
+    }
+
+    pub fn operation_stubs(&self) -> &RwLock<HashMap<String, OperationStub>> {
+        &self.0.operation_stubs
+    }
+
+    pub fn fault_rules(&self) -> &RwLock<Vec<FaultRule>> {
+        &self.0.fault_rules
+    }
+
+    pub fn connection_init_rules(&self) -> &RwLock<Vec<ConnectionInitRule>> {
+        &self.0.connection_init_rules
+    }
+
+    pub fn breakpoints(&self) -> &Breakpoints {
+        &self.0.breakpoints
+    }
+
+    pub fn ws_connections(&self) -> &WsConnections {
+        &self.0.ws_connections
+    }
+
+    pub fn config_changed_sender(&self) -> &broadcast::Sender<ConfigChangedEvent> {
+        &self.0.config_changed_sender
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderName, HeaderValue};
+
+    use super::*;
+
+    #[test]
+    fn test_activate_upstream_profile() {
+        let admin_state = AdminState::new(
+            "http://localhost:8001/api/graphql",
+            "ws://localhost:8001/api/graphql-ws",
+            false,
+            HeaderMap::new(),
+            HeaderMap::new(),
+            0,
+        );
+        admin_state.upstream_profiles().write().insert(
+            "staging".to_string(),
+            UpstreamProfile {
+                name: "staging".to_string(),
+                server_endpoints: GraphQLEndpoints {
+                    graphql_endpoint: "https://staging/api/graphql".to_string(),
+                    graphql_ws_endpoint: "wss://staging/api/graphql-ws".to_string(),
+                },
+                request_headers: HeaderMap::from_iter([(
+                    HeaderName::from_static("authorization"),
+                    HeaderValue::from_static("Bearer foo"),
+                )]),
+                accept_invalid_certificates: true,
+            },
+        );
+
+        assert!(admin_state.activate_upstream_profile("prod").is_none());
+        assert!(admin_state.activate_upstream_profile("staging").is_some());
+
+        let upstream_settings = admin_state.upstream_settings();
+        assert_eq!(
+            upstream_settings.server_endpoints.graphql_endpoint,
+            "https://staging/api/graphql"
+        );
+        assert_eq!(
+            upstream_settings.request_headers["authorization"],
+            "Bearer foo"
+        );
+        assert!(upstream_settings.accept_invalid_certificates);
+        assert_eq!(
+            admin_state.active_upstream_profile().read().as_deref(),
+            Some("staging")
+        );
+
+        admin_state.override_upstream_settings(|_server_endpoints, request_headers| {
+            request_headers.clear();
+        });
+        assert_eq!(
+            admin_state.active_upstream_profile().read().as_deref(),
+            None
+        );
+    }
 }
@@ -1,9 +1,21 @@
 use std::sync::Arc;
 
-use crate::admin_state::AdminState;
+use tokio_tungstenite::Connector;
+
+use crate::{
+    admin_state::AdminState,
+    capture::replay::Cassette,
+    error::CreateAppStateError,
+    ws_tls::{create_insecure_ws_connector, create_ws_connector},
+};
 
 struct AppStateInner {
     server_client: reqwest::Client,
+    insecure_server_client: reqwest::Client,
+    ws_connector: Connector,
+    insecure_ws_connector: Connector,
+    cassette: Option<Cassette>,
+    max_upload_size: usize,
 }
 
 #[derive(Clone)]
@@ -13,10 +25,21 @@ pub struct AppState {
 }
 
 impl AppState {
-    pub fn new(admin_state: AdminState) -> Result<Self, reqwest::Error> {
+    pub fn new(
+        admin_state: AdminState,
+        cassette: Option<Cassette>,
+        max_upload_size: usize,
+    ) -> Result<Self, CreateAppStateError> {
         Ok(Self {
             params: Arc::new(AppStateInner {
                 server_client: reqwest::ClientBuilder::new().build()?,
+                insecure_server_client: reqwest::ClientBuilder::new()
+                    .danger_accept_invalid_certs(true)
+                    .build()?,
+                ws_connector: create_ws_connector()?,
+                insecure_ws_connector: create_insecure_ws_connector()?,
+                cassette,
+                max_upload_size,
             }),
             admin_state,
         })
@@ -26,7 +49,27 @@ impl AppState {
         &self.admin_state
     }
 
-    pub fn server_client(&self) -> &reqwest::Client {
-        &self.params.server_client
+    pub fn server_client(&self, accept_invalid_certificates: bool) -> &reqwest::Client {
+        if accept_invalid_certificates {
+            &self.params.insecure_server_client
+        } else {
+            &self.params.server_client
+        }
+    }
+
+    pub fn ws_connector(&self, accept_invalid_certificates: bool) -> Connector {
+        if accept_invalid_certificates {
+            self.params.insecure_ws_connector.clone()
+        } else {
+            self.params.ws_connector.clone()
+        }
+    }
+
+    pub fn cassette(&self) -> Option<&Cassette> {
+        self.params.cassette.as_ref()
+    }
+
+    pub fn max_upload_size(&self) -> usize {
+        self.params.max_upload_size
     }
 }
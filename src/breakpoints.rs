@@ -0,0 +1,377 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{oneshot, watch};
+
+use crate::{
+    log_location,
+    model::{
+        enums::{
+            connection_type::ConnectionType, message_direction::MessageDirection,
+            operation_type::GraphQLOperationType,
+        },
+        types::{breakpoint_rule::BreakpointRule, paused_message::PausedMessage},
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakpointResolution {
+    Resume,
+    Edit(serde_json::Value),
+    Drop,
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakpointTarget {
+    pub connection_id: Arc<String>,
+    pub connection_type: ConnectionType,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+}
+
+struct PendingMessage {
+    paused_message: PausedMessage,
+    resolution_sender: oneshot::Sender<BreakpointResolution>,
+}
+
+struct BreakpointsInner {
+    rules: RwLock<Vec<BreakpointRule>>,
+    pending_messages: Mutex<Vec<PendingMessage>>,
+    paused_messages_sender: watch::Sender<Vec<PausedMessage>>,
+    auto_resume_timeout: RwLock<Option<Duration>>,
+}
+
+#[derive(Clone)]
+pub struct Breakpoints(Arc<BreakpointsInner>);
+
+// the pending message is removed when the pause ends, also when its request is cancelled
+struct PendingMessageGuard<'a> {
+    breakpoints: &'a Breakpoints,
+    id: String,
+}
+
+impl Drop for PendingMessageGuard<'_> {
+    fn drop(&mut self) {
+        let mut pending_messages = self.breakpoints.0.pending_messages.lock();
+        if let Some(index) = pending_messages
+            .iter()
+            .position(|pending_message| pending_message.paused_message.id == self.id)
+        {
+            pending_messages.remove(index);
+            self.breakpoints.publish(&pending_messages);
+        }
+    }
+}
+
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self(Arc::new(BreakpointsInner {
+            rules: RwLock::new(Vec::new()),
+            pending_messages: Mutex::new(Vec::new()),
+            paused_messages_sender: watch::Sender::new(Vec::new()),
+            auto_resume_timeout: RwLock::new(None),
+        }))
+    }
+
+    pub fn rules(&self) -> &RwLock<Vec<BreakpointRule>> {
+        &self.0.rules
+    }
+
+    pub fn set_auto_resume_timeout(&self, auto_resume_timeout: Option<Duration>) {
+        *self.0.auto_resume_timeout.write() = auto_resume_timeout;
+    }
+
+    pub fn paused_messages(&self) -> Vec<PausedMessage> {
+        self.0.paused_messages_sender.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Vec<PausedMessage>> {
+        self.0.paused_messages_sender.subscribe()
+    }
+
+    pub fn is_matching(
+        &self,
+        target: &BreakpointTarget,
+        message_direction: MessageDirection,
+    ) -> bool {
+        self.0.rules.read().iter().any(|rule| {
+            rule.is_matching(
+                target.connection_type,
+                message_direction,
+                target.operation_name.as_deref(),
+                target.operation_type,
+            )
+        })
+    }
+
+    pub async fn pause_if_matching(
+        &self,
+        target: &BreakpointTarget,
+        message_direction: MessageDirection,
+        message: serde_json::Value,
+    ) -> BreakpointResolution {
+        if !self.is_matching(target, message_direction) {
+            return BreakpointResolution::Resume;
+        }
+
+        let (resolution_sender, resolution_receiver) = oneshot::channel();
+        let paused_message = PausedMessage {
+            id: uuid::Uuid::new_v4().as_hyphenated().to_string(),
+            connection_id: target.connection_id.to_string(),
+            connection_type: target.connection_type,
+            message_direction,
+            operation_name: target.operation_name.clone(),
+            operation_type: target.operation_type,
+            message,
+            timestamp: humantime::format_rfc3339_micros(SystemTime::now()).to_string(),
+        };
+
+        log::debug!(
+            "pausing message at a breakpoint, id = '{}'",
+            paused_message.id
+        );
+
+        let _pending_message_guard = PendingMessageGuard {
+            breakpoints: self,
+            id: paused_message.id.clone(),
+        };
+        {
+            let mut pending_messages = self.0.pending_messages.lock();
+            pending_messages.push(PendingMessage {
+                paused_message,
+                resolution_sender,
+            });
+            self.publish(&pending_messages);
+        }
+
+        // the message is resumed when the pending message is discarded without a resolution
+        let auto_resume_timeout = *self.0.auto_resume_timeout.read();
+        match auto_resume_timeout {
+            Some(auto_resume_timeout) => {
+                match tokio::time::timeout(auto_resume_timeout, resolution_receiver).await {
+                    Ok(resolution) => resolution.unwrap_or(BreakpointResolution::Resume),
+                    Err(_elapsed) => {
+                        log::warn!(
+                            "{}, the paused message is resumed after the timeout = {auto_resume_timeout:?}",
+                            log_location!()
+                        );
+                        BreakpointResolution::Resume
+                    }
+                }
+            }
+            None => resolution_receiver
+                .await
+                .unwrap_or(BreakpointResolution::Resume),
+        }
+    }
+
+    pub fn resolve(&self, id: &str, resolution: BreakpointResolution) -> Option<PausedMessage> {
+        let mut pending_messages = self.0.pending_messages.lock();
+        let index = pending_messages
+            .iter()
+            .position(|pending_message| pending_message.paused_message.id == id)?;
+
+        let pending_message = pending_messages.remove(index);
+        self.publish(&pending_messages);
+
+        let _ = pending_message.resolution_sender.send(resolution);
+
+        Some(pending_message.paused_message)
+    }
+
+    pub fn resume_unmatched(&self) -> Vec<PausedMessage> {
+        let rules = self.0.rules.read();
+        let mut pending_messages = self.0.pending_messages.lock();
+
+        let (matched_messages, unmatched_messages) = std::mem::take(&mut *pending_messages)
+            .into_iter()
+            .partition::<Vec<_>, _>(|pending_message| {
+                let paused_message = &pending_message.paused_message;
+                rules.iter().any(|rule| {
+                    rule.is_matching(
+                        paused_message.connection_type,
+                        paused_message.message_direction,
+                        paused_message.operation_name.as_deref(),
+                        paused_message.operation_type,
+                    )
+                })
+            });
+        *pending_messages = matched_messages;
+        self.publish(&pending_messages);
+
+        unmatched_messages
+            .into_iter()
+            .map(|pending_message| {
+                let _ = pending_message
+                    .resolution_sender
+                    .send(BreakpointResolution::Resume);
+                pending_message.paused_message
+            })
+            .collect()
+    }
+
+    pub fn resume_all(&self) -> Vec<PausedMessage> {
+        let mut pending_messages = self.0.pending_messages.lock();
+        let resumed_messages = std::mem::take(&mut *pending_messages);
+        self.publish(&pending_messages);
+
+        resumed_messages
+            .into_iter()
+            .map(|pending_message| {
+                let _ = pending_message
+                    .resolution_sender
+                    .send(BreakpointResolution::Resume);
+                pending_message.paused_message
+            })
+            .collect()
+    }
+
+    fn publish(&self, pending_messages: &[PendingMessage]) {
+        self.0.paused_messages_sender.send_replace(
+            pending_messages
+                .iter()
+                .map(|pending_message| pending_message.paused_message.clone())
+                .collect(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::inputs::breakpoint_rule_input::BreakpointRuleInput;
+
+    use super::*;
+
+    fn create_breakpoint_target() -> BreakpointTarget {
+        BreakpointTarget {
+            connection_id: Arc::new("1".to_string()),
+            connection_type: ConnectionType::Http,
+            operation_name: Some("Foo".to_string()),
+            operation_type: Some(GraphQLOperationType::Query),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pause_if_matching() {
+        let breakpoints = Breakpoints::new();
+        let target = create_breakpoint_target();
+
+        assert_eq!(
+            breakpoints
+                .pause_if_matching(&target, MessageDirection::Request, serde_json::json!({}))
+                .await,
+            BreakpointResolution::Resume
+        );
+
+        breakpoints
+            .rules()
+            .write()
+            .push(BreakpointRule::new(BreakpointRuleInput {
+                connection_type: None,
+                message_direction: Some(MessageDirection::Request),
+                operation_name: Some("Foo".to_string()),
+                operation_type: None,
+            }));
+        assert!(!breakpoints.is_matching(&target, MessageDirection::Response));
+
+        let paused = {
+            let breakpoints = breakpoints.clone();
+            let target = target.clone();
+            tokio::spawn(async move {
+                breakpoints
+                    .pause_if_matching(&target, MessageDirection::Request, serde_json::json!({}))
+                    .await
+            })
+        };
+
+        let mut receiver = breakpoints.subscribe();
+        let paused_messages = receiver
+            .wait_for(|paused_messages| !paused_messages.is_empty())
+            .await
+            .unwrap()
+            .clone();
+        assert_eq!(paused_messages.len(), 1);
+        assert_eq!(paused_messages[0].operation_name.as_deref(), Some("Foo"));
+
+        assert!(breakpoints
+            .resolve("unknown", BreakpointResolution::Drop)
+            .is_none());
+        assert!(breakpoints
+            .resolve(
+                &paused_messages[0].id,
+                BreakpointResolution::Edit(serde_json::json!({ "foo": 1 }))
+            )
+            .is_some());
+        assert_eq!(
+            paused.await.unwrap(),
+            BreakpointResolution::Edit(serde_json::json!({ "foo": 1 }))
+        );
+        assert!(breakpoints.paused_messages().is_empty());
+    }
+
+    fn create_breakpoint_rule() -> BreakpointRule {
+        BreakpointRule::new(BreakpointRuleInput {
+            connection_type: None,
+            message_direction: Some(MessageDirection::Request),
+            operation_name: Some("Foo".to_string()),
+            operation_type: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pause_if_matching_timeout_and_cancellation() {
+        let breakpoints = Breakpoints::new();
+        let target = create_breakpoint_target();
+        breakpoints.rules().write().push(create_breakpoint_rule());
+
+        breakpoints.set_auto_resume_timeout(Some(Duration::from_millis(10)));
+        assert_eq!(
+            breakpoints
+                .pause_if_matching(&target, MessageDirection::Request, serde_json::json!({}))
+                .await,
+            BreakpointResolution::Resume
+        );
+        assert!(breakpoints.paused_messages().is_empty());
+
+        breakpoints.set_auto_resume_timeout(None);
+        let paused = breakpoints.pause_if_matching(
+            &target,
+            MessageDirection::Request,
+            serde_json::json!({}),
+        );
+        assert!(tokio::time::timeout(Duration::from_millis(10), paused)
+            .await
+            .is_err());
+        assert!(breakpoints.paused_messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resume_unmatched() {
+        let breakpoints = Breakpoints::new();
+        let target = create_breakpoint_target();
+        breakpoints.rules().write().push(create_breakpoint_rule());
+
+        let paused = {
+            let breakpoints = breakpoints.clone();
+            tokio::spawn(async move {
+                breakpoints
+                    .pause_if_matching(&target, MessageDirection::Request, serde_json::json!({}))
+                    .await
+            })
+        };
+        breakpoints
+            .subscribe()
+            .wait_for(|paused_messages| !paused_messages.is_empty())
+            .await
+            .unwrap();
+
+        assert!(breakpoints.resume_unmatched().is_empty());
+        breakpoints.rules().write().clear();
+        assert_eq!(breakpoints.resume_unmatched().len(), 1);
+        assert_eq!(paused.await.unwrap(), BreakpointResolution::Resume);
+        assert!(breakpoints.paused_messages().is_empty());
+    }
+}
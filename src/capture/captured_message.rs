@@ -0,0 +1,307 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::model::{
+    enums::{
+        connection_type::ConnectionType, http_method::HttpMethod,
+        message_direction::MessageDirection, message_origin::MessageOrigin,
+    },
+    types::{headers::Headers, message::Message},
+};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedMessage {
+    pub event_id: u64,
+    pub connection_id: String,
+    pub sequence_counter: u64,
+    pub connection_type: ConnectionType,
+    pub message_direction: MessageDirection,
+    pub message: serde_json::Value,
+    pub transmitted_headers: Option<Vec<CapturedHeader>>,
+    pub server_endpoint_url: String,
+    #[serde(with = "rfc3339")]
+    pub timestamp: SystemTime,
+    pub latency_ms: Option<f64>,
+    #[serde(default)]
+    pub origin: Option<MessageOrigin>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replayed_event_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_method: Option<HttpMethod>,
+}
+
+impl From<&Message> for CapturedMessage {
+    fn from(message: &Message) -> Self {
+        Self {
+            event_id: message.event_id,
+            connection_id: message.connection_id.as_ref().clone(),
+            sequence_counter: message.sequence_counter,
+            connection_type: message.connection_type,
+            message_direction: message.message_direction,
+            message: message.message.as_ref().clone(),
+            transmitted_headers: message.transmitted_headers.as_ref().map(|headers| {
+                headers
+                    .header_vec()
+                    .iter()
+                    .map(|header| CapturedHeader {
+                        name: header.name.as_header_name().to_string(),
+                        value: String::from_utf8_lossy(header.value.as_header_value().as_bytes())
+                            .into_owned(),
+                    })
+                    .collect()
+            }),
+            server_endpoint_url: message.server_endpoint_url.as_ref().clone(),
+            timestamp: message.timestamp,
+            latency_ms: message
+                .latency
+                .map(|latency| latency.as_secs_f64() * 1000.0),
+            origin: Some(message.origin),
+            replayed_event_id: message.replayed_event_id,
+            status_code: message.status_code,
+            http_method: message.http_method,
+        }
+    }
+}
+
+// also redacted as the payload keys of connection_init frames
+const SENSITIVE_HEADER_NAMES: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+const REDACTED_HEADER_VALUE: &str = "[REDACTED]";
+
+impl CapturedMessage {
+    pub fn redact_sensitive_headers(&mut self) {
+        for header in self.transmitted_headers.iter_mut().flatten() {
+            if SENSITIVE_HEADER_NAMES
+                .iter()
+                .any(|name| header.name.eq_ignore_ascii_case(name))
+            {
+                header.value = REDACTED_HEADER_VALUE.to_string();
+            }
+        }
+
+        if self.connection_type != ConnectionType::Ws || self.message["type"] != "connection_init" {
+            return;
+        }
+        let Some(payload) = self
+            .message
+            .get_mut("payload")
+            .and_then(serde_json::Value::as_object_mut)
+        else {
+            return;
+        };
+        for (key, value) in payload.iter_mut() {
+            if SENSITIVE_HEADER_NAMES
+                .iter()
+                .any(|name| key.eq_ignore_ascii_case(name))
+            {
+                *value = REDACTED_HEADER_VALUE.into();
+            }
+        }
+    }
+
+    pub fn header_map(&self) -> Option<HeaderMap> {
+        self.transmitted_headers.as_ref().map(|headers| {
+            headers
+                .iter()
+                .filter_map(|header| {
+                    Some((
+                        HeaderName::try_from(header.name.as_str()).ok()?,
+                        HeaderValue::try_from(header.value.as_str()).ok()?,
+                    ))
+                })
+                .collect()
+        })
+    }
+}
+
+impl From<CapturedMessage> for Message {
+    fn from(captured_message: CapturedMessage) -> Self {
+        let transmitted_headers = captured_message
+            .header_map()
+            .map(|header_map| Arc::new(Headers::from_header_map(header_map)));
+
+        Self {
+            event_id: captured_message.event_id,
+            sequence_counter: captured_message.sequence_counter,
+            message: Arc::new(captured_message.message),
+            connection_type: captured_message.connection_type,
+            message_direction: captured_message.message_direction,
+            connection_id: Arc::new(captured_message.connection_id),
+            transmitted_headers,
+            server_endpoint_url: Arc::new(captured_message.server_endpoint_url),
+            timestamp: captured_message.timestamp,
+            latency: captured_message
+                .latency_ms
+                .and_then(|latency_ms| Duration::try_from_secs_f64(latency_ms / 1000.0).ok()),
+            origin: captured_message.origin.unwrap_or_else(|| {
+                MessageOrigin::from_message_direction(captured_message.message_direction)
+            }),
+            replayed_event_id: captured_message.replayed_event_id,
+            status_code: captured_message.status_code,
+            http_method: captured_message.http_method,
+        }
+    }
+}
+
+pub fn payload_to_text(payload: &serde_json::Value) -> String {
+    match payload {
+        serde_json::Value::String(text) => text.clone(),
+        payload => payload.to_string(),
+    }
+}
+
+mod rfc3339 {
+    use std::time::SystemTime;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        timestamp: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&humantime::format_rfc3339_micros(*timestamp))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let timestamp = String::deserialize(deserializer)?;
+        humantime::parse_rfc3339(&timestamp).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captured_message_serde_roundtrip() {
+        let mut header_map = HeaderMap::new();
+        header_map.insert("authorization", "Bearer foo".parse().unwrap());
+
+        let message = Message {
+            event_id: 7,
+            sequence_counter: 1,
+            message: Arc::new(serde_json::json!({ "data": { "foo": 1 } })),
+            connection_type: ConnectionType::Http,
+            message_direction: MessageDirection::Response,
+            connection_id: Arc::new("connection".to_string()),
+            transmitted_headers: Some(Arc::new(Headers::from_header_map(header_map))),
+            server_endpoint_url: Arc::new("http://localhost/api/graphql".to_string()),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456),
+            latency: Some(Duration::from_millis(12)),
+            origin: MessageOrigin::Stub,
+            replayed_event_id: Some(3),
+            status_code: Some(201),
+            http_method: None,
+        };
+
+        let captured_message = CapturedMessage::from(&message);
+        let line = serde_json::to_string(&captured_message).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&line).unwrap(),
+            serde_json::json!({
+                "eventId": 7,
+                "connectionId": "connection",
+                "sequenceCounter": 1,
+                "connectionType": "HTTP",
+                "messageDirection": "RESPONSE",
+                "message": { "data": { "foo": 1 } },
+                "transmittedHeaders": [{ "name": "authorization", "value": "Bearer foo" }],
+                "serverEndpointUrl": "http://localhost/api/graphql",
+                "timestamp": "2023-11-14T22:13:20.123456Z",
+                "latencyMs": 12.0,
+                "origin": "STUB",
+                "replayedEventId": 3,
+                "statusCode": 201,
+            })
+        );
+        assert_eq!(
+            serde_json::from_str::<CapturedMessage>(&line).unwrap(),
+            captured_message
+        );
+        assert_eq!(
+            CapturedMessage::from(&Message::from(captured_message.clone())),
+            captured_message
+        );
+    }
+
+    #[test]
+    fn test_redact_sensitive_headers() {
+        let mut captured_message = CapturedMessage::from(&Message {
+            transmitted_headers: Some(Arc::new(Headers::from_header_map(HeaderMap::from_iter([
+                (
+                    HeaderName::from_static("authorization"),
+                    HeaderValue::from_static("Bearer foo"),
+                ),
+                (
+                    HeaderName::from_static("cookie"),
+                    HeaderValue::from_static("session=foo"),
+                ),
+                (
+                    HeaderName::from_static("content-type"),
+                    HeaderValue::from_static("application/json"),
+                ),
+            ])))),
+            ..Message::for_test()
+        });
+        captured_message.redact_sensitive_headers();
+
+        assert_eq!(
+            captured_message.transmitted_headers,
+            Some(vec![
+                CapturedHeader {
+                    name: "authorization".to_string(),
+                    value: REDACTED_HEADER_VALUE.to_string(),
+                },
+                CapturedHeader {
+                    name: "cookie".to_string(),
+                    value: REDACTED_HEADER_VALUE.to_string(),
+                },
+                CapturedHeader {
+                    name: "content-type".to_string(),
+                    value: "application/json".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_redact_sensitive_connection_init_payload() {
+        let mut captured_message = CapturedMessage::from(&Message {
+            connection_type: ConnectionType::Ws,
+            message: Arc::new(serde_json::json!({
+                "type": "connection_init",
+                "payload": { "Authorization": "Bearer foo", "clientName": "foo" },
+            })),
+            ..Message::for_test()
+        });
+        captured_message.redact_sensitive_headers();
+
+        assert_eq!(
+            captured_message.message,
+            serde_json::json!({
+                "type": "connection_init",
+                "payload": { "Authorization": REDACTED_HEADER_VALUE, "clientName": "foo" },
+            })
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    endpoints::{graphql_proxy::create_query_params, response_body_capture::frame_captured_texts},
+    model::{
+        enums::{
+            connection_type::ConnectionType, http_method::HttpMethod,
+            message_direction::MessageDirection, message_origin::MessageOrigin,
+        },
+        inputs::message_filter::MessageFilter,
+        types::message::Message,
+    },
+};
+
+use super::captured_message::payload_to_text;
+
+fn group_messages_by_connection_id(messages: &[Message]) -> Vec<Vec<&Message>> {
+    let mut connections: Vec<Vec<&Message>> = Vec::new();
+    let mut connection_indices = HashMap::<&str, usize>::new();
+
+    for message in messages {
+        match connection_indices.get(message.connection_id.as_str()) {
+            Some(index) => connections[*index].push(message),
+            None => {
+                connection_indices.insert(message.connection_id.as_str(), connections.len());
+                connections.push(vec![message]);
+            }
+        }
+    }
+
+    for connection in connections.iter_mut() {
+        connection.sort_by_key(|message| message.sequence_counter);
+    }
+
+    connections
+}
+
+fn format_timestamp(timestamp: SystemTime) -> String {
+    humantime::format_rfc3339_millis(timestamp).to_string()
+}
+
+fn duration_to_ms(duration: Option<Duration>) -> f64 {
+    duration
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
+fn headers_to_har(message: Option<&Message>) -> Vec<serde_json::Value> {
+    message
+        .and_then(|message| message.transmitted_headers.as_ref())
+        .map(|headers| {
+            headers
+                .header_vec()
+                .iter()
+                .map(|header| {
+                    serde_json::json!({
+                        "name": header.name.as_header_name().as_str(),
+                        "value": String::from_utf8_lossy(header.value.as_header_value().as_bytes()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn content_type_of(message: Option<&Message>) -> String {
+    message
+        .and_then(|message| message.transmitted_headers.as_ref())
+        .and_then(|headers| {
+            headers
+                .header_vec()
+                .iter()
+                .find(|header| header.name.as_header_name() == http::header::CONTENT_TYPE)
+                .map(|header| {
+                    String::from_utf8_lossy(header.value.as_header_value().as_bytes()).into_owned()
+                })
+        })
+        .unwrap_or_else(|| "application/json".to_string())
+}
+
+// the last request is the one sent to the server, a response edited at a breakpoint replaces
+// the response of the server, the other responses are the parts of the body
+fn create_http_entry(connection: &[&Message]) -> Option<serde_json::Value> {
+    let requests = connection
+        .iter()
+        .filter(|message| message.message_direction == MessageDirection::Request)
+        .collect::<Vec<_>>();
+    let (first_request, request) = (requests.first()?, requests.last()?);
+    let responses = connection
+        .iter()
+        .filter(|message| message.message_direction == MessageDirection::Response)
+        .copied()
+        .collect::<Vec<_>>();
+    let responses = match responses
+        .iter()
+        .rposition(|response| response.origin == MessageOrigin::Breakpoint)
+    {
+        Some(position) => &responses[position..],
+        None => &responses[..],
+    };
+    let response = responses.first().copied();
+
+    let request_text = payload_to_text(&request.message);
+    let time = duration_to_ms(responses.last().and_then(|response| response.latency));
+
+    let response_json = match response {
+        Some(response) => {
+            let response_texts = responses
+                .iter()
+                .map(|response| payload_to_text(&response.message))
+                .collect::<Vec<_>>();
+            let response_text = frame_captured_texts(
+                Some(&content_type_of(Some(response))),
+                response_texts.iter().map(String::as_str),
+            );
+            let status = response
+                .status_code
+                .and_then(|status_code| http::StatusCode::from_u16(status_code).ok())
+                .unwrap_or(http::StatusCode::OK);
+            serde_json::json!({
+                "status": status.as_u16(),
+                "statusText": status.canonical_reason().unwrap_or_default(),
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": headers_to_har(Some(response)),
+                "content": {
+                    "size": response_text.len(),
+                    "mimeType": content_type_of(Some(response)),
+                    "text": response_text,
+                },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": response_text.len(),
+            })
+        }
+        None => serde_json::json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "x-unknown" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+        }),
+    };
+
+    let request_json = match request.http_method.unwrap_or(HttpMethod::Post) {
+        HttpMethod::Get => {
+            let query_params = create_query_params(&request.message);
+            let url = reqwest::Url::parse_with_params(
+                request.server_endpoint_url.as_str(),
+                &query_params,
+            )
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| request.server_endpoint_url.as_ref().clone());
+
+            serde_json::json!({
+                "method": HttpMethod::Get.as_str(),
+                "url": url,
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": headers_to_har(Some(request)),
+                "queryString": query_params
+                    .iter()
+                    .map(|(name, value)| serde_json::json!({ "name": name, "value": value }))
+                    .collect::<Vec<_>>(),
+                "headersSize": -1,
+                "bodySize": 0,
+            })
+        }
+        HttpMethod::Post => serde_json::json!({
+            "method": HttpMethod::Post.as_str(),
+            "url": request.server_endpoint_url.as_str(),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers_to_har(Some(request)),
+            "queryString": [],
+            "postData": {
+                "mimeType": "application/json",
+                "text": request_text,
+            },
+            "headersSize": -1,
+            "bodySize": request_text.len(),
+        }),
+    };
+
+    Some(serde_json::json!({
+        "startedDateTime": format_timestamp(first_request.timestamp),
+        "time": time,
+        "request": request_json,
+        "response": response_json,
+        "cache": {},
+        "timings": { "send": 0, "wait": time, "receive": 0 },
+        "_connectionId": request.connection_id.as_str(),
+    }))
+}
+
+fn create_ws_entry(connection: &[&Message]) -> Option<serde_json::Value> {
+    let handshake_request = connection.iter().find(|message| {
+        message.message_direction == MessageDirection::Request && message.message.is_null()
+    })?;
+    let handshake_response = connection
+        .iter()
+        .find(|message| {
+            message.message_direction == MessageDirection::Response && message.message.is_null()
+        })
+        .copied();
+
+    let web_socket_messages = connection
+        .iter()
+        .filter(|message| !message.message.is_null())
+        .map(|message| {
+            serde_json::json!({
+                "type": match message.message_direction {
+                    MessageDirection::Request => "send",
+                    MessageDirection::Response => "receive",
+                },
+                "time": message
+                    .timestamp
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64(),
+                "opcode": 1,
+                "data": payload_to_text(&message.message),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let time = duration_to_ms(handshake_response.and_then(|response| response.latency));
+
+    Some(serde_json::json!({
+        "startedDateTime": format_timestamp(handshake_request.timestamp),
+        "time": time,
+        "request": {
+            "method": "GET",
+            "url": handshake_request.server_endpoint_url.as_str(),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers_to_har(Some(handshake_request)),
+            "queryString": [],
+            "headersSize": -1,
+            "bodySize": 0,
+        },
+        "response": {
+            "status": if handshake_response.is_some() { 101 } else { 0 },
+            "statusText": if handshake_response.is_some() { "Switching Protocols" } else { "" },
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers_to_har(handshake_response),
+            "content": { "size": 0, "mimeType": "x-unknown" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": 0,
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": time, "receive": 0 },
+        "_resourceType": "websocket",
+        "_webSocketMessages": web_socket_messages,
+        "_connectionId": handshake_request.connection_id.as_str(),
+    }))
+}
+
+// the filters select whole exchanges, an exchange is kept when any of its messages is allowed, so
+// e.g., a response filter does not remove the requests the entries are built from
+pub fn create_har(messages: &[Message], message_filters: &[MessageFilter]) -> serde_json::Value {
+    let entries = group_messages_by_connection_id(messages)
+        .iter()
+        .filter(|connection| {
+            connection.iter().any(|message| {
+                MessageFilter::is_message_allowed_by_filters(message_filters, message)
+            })
+        })
+        .filter_map(|connection| match connection[0].connection_type {
+            ConnectionType::Http => create_http_entry(connection),
+            ConnectionType::Ws => create_ws_entry(connection),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "pages": [],
+            "entries": entries,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use http::HeaderMap;
+
+    use crate::model::{enums::filter_type::FilterType, types::headers::Headers};
+
+    use super::*;
+
+    fn create_message(
+        connection_id: &str,
+        sequence_counter: u64,
+        connection_type: ConnectionType,
+        message_direction: MessageDirection,
+        message: serde_json::Value,
+        timestamp_ms: u64,
+    ) -> Message {
+        Message {
+            sequence_counter,
+            message: Arc::new(message),
+            connection_type,
+            message_direction,
+            connection_id: Arc::new(connection_id.to_string()),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(timestamp_ms),
+            latency: match message_direction {
+                MessageDirection::Request => None,
+                MessageDirection::Response => Some(Duration::from_millis(5)),
+            },
+            origin: MessageOrigin::from_message_direction(message_direction),
+            ..Message::for_test()
+        }
+    }
+
+    #[test]
+    fn test_create_har() {
+        let messages = vec![
+            create_message(
+                "http",
+                0,
+                ConnectionType::Http,
+                MessageDirection::Request,
+                serde_json::json!({ "query": "{ foo }" }),
+                1000,
+            ),
+            create_message(
+                "ws",
+                0,
+                ConnectionType::Ws,
+                MessageDirection::Request,
+                serde_json::Value::Null,
+                1001,
+            ),
+            create_message(
+                "http",
+                1,
+                ConnectionType::Http,
+                MessageDirection::Response,
+                serde_json::json!({ "data": { "foo": 1 } }),
+                1005,
+            ),
+            create_message(
+                "ws",
+                1,
+                ConnectionType::Ws,
+                MessageDirection::Response,
+                serde_json::Value::Null,
+                1006,
+            ),
+            create_message(
+                "ws",
+                2,
+                ConnectionType::Ws,
+                MessageDirection::Request,
+                serde_json::json!({ "type": "connection_init" }),
+                1500,
+            ),
+        ];
+
+        let har = create_har(&messages, &[]);
+        let entries = har["log"]["entries"].as_array().unwrap();
+
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0]["startedDateTime"], "1970-01-01T00:00:01.000Z");
+        assert_eq!(entries[0]["time"], 5.0);
+        assert_eq!(entries[0]["request"]["method"], "POST");
+        assert_eq!(
+            entries[0]["request"]["postData"]["text"],
+            r#"{"query":"{ foo }"}"#
+        );
+        assert_eq!(entries[0]["response"]["status"], 200);
+        assert_eq!(
+            entries[0]["response"]["content"]["text"],
+            r#"{"data":{"foo":1}}"#
+        );
+
+        assert_eq!(entries[1]["request"]["method"], "GET");
+        assert_eq!(entries[1]["response"]["status"], 101);
+        assert_eq!(
+            entries[1]["_webSocketMessages"],
+            serde_json::json!([{
+                "type": "send",
+                "time": 1.5,
+                "opcode": 1,
+                "data": r#"{"type":"connection_init"}"#,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_create_har_get_request() {
+        let messages = vec![
+            Message {
+                http_method: Some(HttpMethod::Get),
+                ..create_message(
+                    "http",
+                    0,
+                    ConnectionType::Http,
+                    MessageDirection::Request,
+                    serde_json::json!({ "query": "{ foo }", "variables": { "a": 1 } }),
+                    1000,
+                )
+            },
+            create_message(
+                "http",
+                1,
+                ConnectionType::Http,
+                MessageDirection::Response,
+                serde_json::json!({ "data": { "foo": 1 } }),
+                1005,
+            ),
+        ];
+
+        let har = create_har(&messages, &[]);
+        let request = &har["log"]["entries"][0]["request"];
+
+        assert_eq!(request["method"], "GET");
+        assert_eq!(
+            request["url"],
+            "http://localhost/api/graphql?query=%7B+foo+%7D&variables=%7B%22a%22%3A1%7D"
+        );
+        assert_eq!(
+            request["queryString"],
+            serde_json::json!([
+                { "name": "query", "value": "{ foo }" },
+                { "name": "variables", "value": r#"{"a":1}"# },
+            ])
+        );
+        assert!(request.get("postData").is_none());
+    }
+
+    #[test]
+    fn test_create_har_filters_whole_exchanges() {
+        let messages = vec![
+            create_message(
+                "http",
+                0,
+                ConnectionType::Http,
+                MessageDirection::Request,
+                serde_json::json!({ "query": "{ foo }" }),
+                1000,
+            ),
+            create_message(
+                "http",
+                1,
+                ConnectionType::Http,
+                MessageDirection::Response,
+                serde_json::json!({ "data": { "foo": 1 } }),
+                1005,
+            ),
+            create_message(
+                "ws",
+                0,
+                ConnectionType::Ws,
+                MessageDirection::Request,
+                serde_json::Value::Null,
+                1001,
+            ),
+        ];
+
+        let har = create_har(
+            &messages,
+            &[MessageFilter {
+                filter_type: FilterType::Prohibit,
+                connection_type: None,
+                message_direction: Some(MessageDirection::Request),
+                payload_type: None,
+                min_latency_ms: None,
+            }],
+        );
+        let entries = har["log"]["entries"].as_array().unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["_connectionId"], "http");
+        assert_eq!(
+            entries[0]["request"]["postData"]["text"],
+            r#"{"query":"{ foo }"}"#
+        );
+    }
+
+    #[test]
+    fn test_create_har_edited_request_and_response_parts() {
+        let response_headers = Arc::new(Headers::from_header_map(HeaderMap::from_iter([(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("multipart/mixed; boundary=\"-\""),
+        )])));
+        let messages = vec![
+            create_message(
+                "http",
+                0,
+                ConnectionType::Http,
+                MessageDirection::Request,
+                serde_json::json!({ "query": "{ foo }" }),
+                1000,
+            ),
+            Message {
+                origin: MessageOrigin::Breakpoint,
+                ..create_message(
+                    "http",
+                    1,
+                    ConnectionType::Http,
+                    MessageDirection::Request,
+                    serde_json::json!({ "query": "{ bar }" }),
+                    1002,
+                )
+            },
+            Message {
+                transmitted_headers: Some(response_headers.clone()),
+                ..create_message(
+                    "http",
+                    2,
+                    ConnectionType::Http,
+                    MessageDirection::Response,
+                    serde_json::json!({ "data": { "bar": 1 }, "hasNext": true }),
+                    1005,
+                )
+            },
+            Message {
+                transmitted_headers: Some(response_headers),
+                latency: Some(Duration::from_millis(8)),
+                ..create_message(
+                    "http",
+                    3,
+                    ConnectionType::Http,
+                    MessageDirection::Response,
+                    serde_json::json!({ "hasNext": false }),
+                    1008,
+                )
+            },
+        ];
+
+        let har = create_har(&messages, &[]);
+        let entry = &har["log"]["entries"][0];
+
+        assert_eq!(entry["startedDateTime"], "1970-01-01T00:00:01.000Z");
+        assert_eq!(entry["time"], 8.0);
+        assert_eq!(
+            entry["request"]["postData"]["text"],
+            r#"{"query":"{ bar }"}"#
+        );
+        assert_eq!(
+            entry["response"]["content"]["text"],
+            "\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{\"data\":{\"bar\":1},\"hasNext\":true}\
+             \r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{\"hasNext\":false}\
+             \r\n-----\r\n"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod captured_message;
+pub mod har;
+pub mod ndjson_reader;
+pub mod ndjson_writer;
+pub mod replay;
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::{CannotParseCaptureFileError, CannotReadCaptureFileError, LoadCaptureError};
+
+use super::captured_message::CapturedMessage;
+
+fn collect_capture_files(path: &Path) -> Result<Vec<PathBuf>, CannotReadCaptureFileError> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = std::fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| CannotReadCaptureFileError {
+            path: path.to_path_buf(),
+            source: e,
+        })?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "ndjson")
+        })
+        .collect::<Vec<_>>();
+
+    // the names of the capture files start with their creation time
+    paths.sort();
+
+    Ok(paths)
+}
+
+pub fn load_captured_messages(paths: &[PathBuf]) -> Result<Vec<CapturedMessage>, LoadCaptureError> {
+    let mut captured_messages = Vec::new();
+
+    for path in paths {
+        for path in collect_capture_files(path)? {
+            let content =
+                std::fs::read_to_string(&path).map_err(|e| CannotReadCaptureFileError {
+                    path: path.clone(),
+                    source: e,
+                })?;
+
+            for (line_index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                captured_messages.push(serde_json::from_str(line).map_err(|e| {
+                    CannotParseCaptureFileError {
+                        path: path.clone(),
+                        line: line_index + 1,
+                        source: e,
+                    }
+                })?);
+            }
+        }
+    }
+
+    Ok(captured_messages)
+}
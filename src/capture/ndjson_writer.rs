@@ -0,0 +1,287 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    error::{CannotCreateCaptureDirectoryError, CannotWriteCaptureFileError},
+    log_location,
+    message_sender::MessageSender,
+    model::types::message::Message,
+};
+
+use super::captured_message::CapturedMessage;
+
+#[derive(Debug, Clone)]
+pub struct NdjsonCaptureConfig {
+    pub directory: PathBuf,
+    pub max_file_size: u64,
+    pub rotation_interval: Duration,
+    pub queue_size: usize,
+    pub redact_sensitive_headers: bool,
+}
+
+struct CaptureFile {
+    writer: BufWriter<File>,
+    size: u64,
+    opened_at: Instant,
+}
+
+pub struct RotatingNdjsonWriter {
+    directory: PathBuf,
+    max_file_size: u64,
+    rotation_interval: Duration,
+    file_index: u64,
+    current_file: Option<CaptureFile>,
+}
+
+impl RotatingNdjsonWriter {
+    pub fn new(directory: PathBuf, max_file_size: u64, rotation_interval: Duration) -> Self {
+        Self {
+            directory,
+            max_file_size,
+            rotation_interval,
+            file_index: 0,
+            current_file: None,
+        }
+    }
+
+    pub fn write(
+        &mut self,
+        captured_message: &CapturedMessage,
+    ) -> Result<(), CannotWriteCaptureFileError> {
+        let mut line =
+            serde_json::to_vec(captured_message).map_err(|e| CannotWriteCaptureFileError {
+                path: self.directory.clone(),
+                source: e.into(),
+            })?;
+        line.push(b'\n');
+
+        let line_size = line.len() as u64;
+        if let Some(current_file) = &self.current_file {
+            if current_file.size + line_size > self.max_file_size
+                || current_file.opened_at.elapsed() >= self.rotation_interval
+            {
+                self.flush()?;
+                self.current_file = None;
+            }
+        }
+
+        let current_file = match self.current_file.take() {
+            Some(current_file) => current_file,
+            None => self.open_next_file()?,
+        };
+        let current_file = self.current_file.insert(current_file);
+
+        current_file
+            .writer
+            .write_all(&line)
+            .map_err(|e| CannotWriteCaptureFileError {
+                path: self.directory.clone(),
+                source: e,
+            })?;
+        current_file.size += line_size;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), CannotWriteCaptureFileError> {
+        if let Some(current_file) = &mut self.current_file {
+            current_file
+                .writer
+                .flush()
+                .map_err(|e| CannotWriteCaptureFileError {
+                    path: self.directory.clone(),
+                    source: e,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn open_next_file(&mut self) -> Result<CaptureFile, CannotWriteCaptureFileError> {
+        let timestamp = humantime::format_rfc3339_seconds(SystemTime::now())
+            .to_string()
+            .replace(':', "-");
+        let path = self.directory.join(format!(
+            "messages-{timestamp}-{:04}.ndjson",
+            self.file_index
+        ));
+        self.file_index += 1;
+
+        log::info!("writing captured messages to file = '{}'", path.display());
+
+        let file = File::create_new(&path).map_err(|e| CannotWriteCaptureFileError {
+            path: path.clone(),
+            source: e,
+        })?;
+
+        Ok(CaptureFile {
+            writer: BufWriter::new(file),
+            size: 0,
+            opened_at: Instant::now(),
+        })
+    }
+}
+
+pub fn spawn_ndjson_capture(
+    message_sender: &MessageSender,
+    config: NdjsonCaptureConfig,
+) -> Result<(), CannotCreateCaptureDirectoryError> {
+    std::fs::create_dir_all(&config.directory).map_err(|e| CannotCreateCaptureDirectoryError {
+        path: config.directory.clone(),
+        source: e,
+    })?;
+
+    let (queue_sender, mut queue_receiver) = mpsc::channel(config.queue_size);
+    let mut receiver = message_sender.subscribe();
+
+    let redact_sensitive_headers = config.redact_sensitive_headers;
+    let captured_message = move |message: &Message| {
+        let mut captured_message = CapturedMessage::from(message);
+        if redact_sensitive_headers {
+            captured_message.redact_sensitive_headers();
+        }
+
+        captured_message
+    };
+
+    // the messages are moved from the broadcast channel to a bounded queue, and the queue is
+    // drained by a dedicated thread, so slow disk I/O never stalls the proxy
+    tokio::spawn(async move {
+        let mut dropped_message_count = 0u64;
+
+        loop {
+            match receiver.recv().await {
+                Ok(message) => match queue_sender.try_send(captured_message(&message)) {
+                    Ok(()) => {
+                        if dropped_message_count != 0 {
+                            log::warn!(
+                                "{}, capture queue was full, dropped = {dropped_message_count}",
+                                log_location!()
+                            );
+                            dropped_message_count = 0;
+                        }
+                    }
+                    Err(mpsc::error::TrySendError::Full(_message)) => {
+                        dropped_message_count += 1;
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_message)) => break,
+                },
+                Err(broadcast::error::RecvError::Closed) => break,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!(
+                        "{}, capture lagged behind, skipped message count = {skipped}",
+                        log_location!()
+                    );
+                }
+            }
+        }
+    });
+
+    std::thread::spawn(move || {
+        let mut writer = RotatingNdjsonWriter::new(
+            config.directory,
+            config.max_file_size,
+            config.rotation_interval,
+        );
+
+        while let Some(captured_message) = queue_receiver.blocking_recv() {
+            if let Err(e) = writer.write(&captured_message) {
+                log::error!("{}, {e}, error = {:?}", log_location!(), e.source);
+            }
+
+            if queue_receiver.is_empty() {
+                if let Err(e) = writer.flush() {
+                    log::error!("{}, {e}, error = {:?}", log_location!(), e.source);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use super::*;
+
+    fn create_captured_message(event_id: u64) -> CapturedMessage {
+        CapturedMessage::from(&Message {
+            event_id,
+            message: Arc::new(serde_json::json!({ "query": "{ foo }" })),
+            ..Message::for_test()
+        })
+    }
+
+    fn read_captured_messages(directory: &Path) -> Vec<Vec<u64>> {
+        let mut paths = std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| {
+                std::fs::read_to_string(path)
+                    .unwrap()
+                    .lines()
+                    .map(|line| {
+                        serde_json::from_str::<CapturedMessage>(line)
+                            .unwrap()
+                            .event_id
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rotating_ndjson_writer_rotates_by_size() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let line_size = serde_json::to_vec(&create_captured_message(0))
+            .unwrap()
+            .len() as u64
+            + 1;
+        let mut writer = RotatingNdjsonWriter::new(directory.clone(), line_size * 2, Duration::MAX);
+        for event_id in 0..5 {
+            writer.write(&create_captured_message(event_id)).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(
+            read_captured_messages(&directory),
+            vec![vec![0, 1], vec![2, 3], vec![4]]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_rotating_ndjson_writer_rotates_by_time() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let mut writer = RotatingNdjsonWriter::new(directory.clone(), u64::MAX, Duration::ZERO);
+        for event_id in 0..3 {
+            writer.write(&create_captured_message(event_id)).unwrap();
+        }
+        writer.flush().unwrap();
+
+        assert_eq!(
+            read_captured_messages(&directory),
+            vec![vec![0], vec![1], vec![2]]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{self, AtomicUsize},
+};
+
+use clap::ValueEnum;
+
+use crate::model::enums::{
+    connection_type::ConnectionType, message_direction::MessageDirection,
+    message_origin::MessageOrigin,
+};
+
+use super::captured_message::CapturedMessage;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum ReplayMatchStrictness {
+    Exact,
+    IgnoreVariables,
+    OperationName,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+pub fn normalize_query(query: &str) -> String {
+    let mut normalized = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    let mut pending_separator = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+                pending_separator = true;
+            }
+            c if c.is_whitespace() || c == ',' || c == '\u{feff}' => pending_separator = true,
+            c => {
+                if pending_separator
+                    && is_name_char(c)
+                    && normalized.chars().last().is_some_and(is_name_char)
+                {
+                    normalized.push(' ');
+                }
+                pending_separator = false;
+                normalized.push(c);
+
+                if c == '"' {
+                    let is_block_string = chars.next_if_eq(&'"').is_some();
+                    if is_block_string && chars.next_if_eq(&'"').is_none() {
+                        // empty string ("")
+                        normalized.push('"');
+                        continue;
+                    }
+                    if is_block_string {
+                        normalized.push_str("\"\"");
+                    }
+
+                    let mut closing_quote_count = 0;
+                    while let Some(c) = chars.next() {
+                        normalized.push(c);
+                        match c {
+                            '\\' => {
+                                if let Some(c) = chars.next() {
+                                    normalized.push(c);
+                                }
+                                closing_quote_count = 0;
+                            }
+                            '"' => {
+                                closing_quote_count += 1;
+                                if !is_block_string || closing_quote_count == 3 {
+                                    break;
+                                }
+                            }
+                            _ => closing_quote_count = 0,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    normalized
+}
+
+fn create_match_key(
+    graphql_request: &serde_json::Value,
+    strictness: ReplayMatchStrictness,
+) -> Option<String> {
+    let query = graphql_request.get("query")?.as_str()?;
+    let operation_name = graphql_request
+        .get("operationName")
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+    let variables = match graphql_request.get("variables") {
+        None | Some(serde_json::Value::Null) => serde_json::json!({}),
+        Some(variables) => variables.clone(),
+    };
+
+    let key = match strictness {
+        ReplayMatchStrictness::Exact => {
+            serde_json::json!([normalize_query(query), operation_name, variables])
+        }
+        ReplayMatchStrictness::IgnoreVariables => {
+            serde_json::json!([normalize_query(query), operation_name])
+        }
+        ReplayMatchStrictness::OperationName => serde_json::json!([operation_name]),
+    };
+
+    Some(key.to_string())
+}
+
+struct RecordedResponses<T> {
+    responses: Vec<T>,
+    next_index: AtomicUsize,
+}
+
+impl<T: Clone> RecordedResponses<T> {
+    // the recorded responses are served in the order of recording, the last one is repeated
+    fn next(&self) -> Option<T> {
+        let index = self.next_index.fetch_add(1, atomic::Ordering::SeqCst);
+        self.responses
+            .get(index)
+            .or_else(|| self.responses.last())
+            .cloned()
+    }
+}
+
+pub struct Cassette {
+    strictness: ReplayMatchStrictness,
+    http_responses: HashMap<String, RecordedResponses<Vec<CapturedMessage>>>,
+    ws_responses: HashMap<String, RecordedResponses<Vec<serde_json::Value>>>,
+}
+
+impl Cassette {
+    pub fn new(captured_messages: Vec<CapturedMessage>, strictness: ReplayMatchStrictness) -> Self {
+        let mut connections: Vec<Vec<CapturedMessage>> = Vec::new();
+        let mut connection_indices = HashMap::<String, usize>::new();
+        for captured_message in captured_messages {
+            match connection_indices.get(&captured_message.connection_id) {
+                Some(index) => connections[*index].push(captured_message),
+                None => {
+                    connection_indices
+                        .insert(captured_message.connection_id.clone(), connections.len());
+                    connections.push(vec![captured_message]);
+                }
+            }
+        }
+
+        let mut http_responses = HashMap::<String, Vec<Vec<CapturedMessage>>>::new();
+        let mut ws_responses = HashMap::<String, Vec<Vec<serde_json::Value>>>::new();
+
+        for mut connection in connections {
+            connection.sort_by_key(|captured_message| captured_message.sequence_counter);
+
+            match connection[0].connection_type {
+                ConnectionType::Http => {
+                    let request = connection.iter().find(|captured_message| {
+                        captured_message.message_direction == MessageDirection::Request
+                    });
+                    let mut responses = connection
+                        .iter()
+                        .filter(|captured_message| {
+                            captured_message.message_direction == MessageDirection::Response
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if let Some(position) = responses
+                        .iter()
+                        .rposition(|response| response.origin == Some(MessageOrigin::Breakpoint))
+                    {
+                        responses.drain(..position);
+                    }
+
+                    if let (Some(request), false) = (request, responses.is_empty()) {
+                        if let Some(key) = create_match_key(&request.message, strictness) {
+                            http_responses.entry(key).or_default().push(responses);
+                        }
+                    }
+                }
+                ConnectionType::Ws => {
+                    for (index, captured_message) in connection.iter().enumerate() {
+                        let frame = &captured_message.message;
+                        let is_operation_frame = captured_message.message_direction
+                            == MessageDirection::Request
+                            && matches!(
+                                frame.get("type").and_then(|ty| ty.as_str()),
+                                Some("subscribe" | "start")
+                            );
+                        if !is_operation_frame {
+                            continue;
+                        }
+
+                        let Some(key) = frame
+                            .get("payload")
+                            .and_then(|payload| create_match_key(payload, strictness))
+                        else {
+                            continue;
+                        };
+
+                        let id = frame.get("id");
+                        let response_frames = connection[index + 1..]
+                            .iter()
+                            .filter(|captured_message| {
+                                captured_message.message_direction == MessageDirection::Response
+                                    && captured_message.message.get("id") == id
+                            })
+                            .map(|captured_message| captured_message.message.clone())
+                            .collect();
+
+                        ws_responses.entry(key).or_default().push(response_frames);
+                    }
+                }
+            }
+        }
+
+        fn into_recorded_responses<T>(
+            responses: HashMap<String, Vec<T>>,
+        ) -> HashMap<String, RecordedResponses<T>> {
+            responses
+                .into_iter()
+                .map(|(key, responses)| {
+                    (
+                        key,
+                        RecordedResponses {
+                            responses,
+                            next_index: AtomicUsize::new(0),
+                        },
+                    )
+                })
+                .collect()
+        }
+
+        Self {
+            strictness,
+            http_responses: into_recorded_responses(http_responses),
+            ws_responses: into_recorded_responses(ws_responses),
+        }
+    }
+
+    pub fn find_http_response(
+        &self,
+        graphql_request: &serde_json::Value,
+    ) -> Option<Vec<CapturedMessage>> {
+        self.http_responses
+            .get(&create_match_key(graphql_request, self.strictness)?)?
+            .next()
+    }
+
+    pub fn find_ws_response_frames(
+        &self,
+        graphql_request: &serde_json::Value,
+        id: &serde_json::Value,
+    ) -> Option<Vec<serde_json::Value>> {
+        let mut frames = self
+            .ws_responses
+            .get(&create_match_key(graphql_request, self.strictness)?)?
+            .next()?;
+
+        for frame in frames.iter_mut() {
+            if let serde_json::Value::Object(map) = frame {
+                map.insert("id".to_string(), id.clone());
+            }
+        }
+
+        Some(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::model::types::message::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("query  Foo($a: Int, $b: [String!]) {\n  foo(a: $a) { bar }\n}"),
+            "query Foo($a:Int$b:[String!]){foo(a:$a){bar}}"
+        );
+        assert_eq!(
+            normalize_query("{ foo # comment\n bar ...on Baz { qux } }"),
+            "{foo bar...on Baz{qux}}"
+        );
+        assert_eq!(
+            normalize_query(r#"{ foo(a: "x,  y", b: "", c: """ a "" b """) }"#),
+            r#"{foo(a:"x,  y"b:""c:""" a "" b """)}"#
+        );
+        assert_eq!(
+            normalize_query(r#"{ foo(a: "\"  \\") }"#),
+            r#"{foo(a:"\"  \\")}"#
+        );
+    }
+
+    fn create_captured_message(
+        connection_id: &str,
+        sequence_counter: u64,
+        connection_type: ConnectionType,
+        message_direction: MessageDirection,
+        message: serde_json::Value,
+    ) -> CapturedMessage {
+        CapturedMessage::from(&Message {
+            sequence_counter,
+            message: Arc::new(message),
+            connection_type,
+            message_direction,
+            connection_id: Arc::new(connection_id.to_string()),
+            ..Message::for_test()
+        })
+    }
+
+    fn create_http_exchange(
+        connection_id: &str,
+        request: serde_json::Value,
+        response: serde_json::Value,
+    ) -> Vec<CapturedMessage> {
+        vec![
+            create_captured_message(
+                connection_id,
+                0,
+                ConnectionType::Http,
+                MessageDirection::Request,
+                request,
+            ),
+            create_captured_message(
+                connection_id,
+                1,
+                ConnectionType::Http,
+                MessageDirection::Response,
+                response,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_cassette_http_responses() {
+        let captured_messages = [
+            create_http_exchange(
+                "1",
+                serde_json::json!({ "query": "query Foo($a: Int) { foo(a: $a) }", "operationName": "Foo", "variables": { "a": 1 } }),
+                serde_json::json!({ "data": { "foo": 1 } }),
+            ),
+            create_http_exchange(
+                "2",
+                serde_json::json!({ "query": "query Foo($a: Int) { foo(a: $a) }", "operationName": "Foo", "variables": { "a": 2 } }),
+                serde_json::json!({ "data": { "foo": 2 } }),
+            ),
+        ]
+        .concat();
+
+        let exact = Cassette::new(captured_messages.clone(), ReplayMatchStrictness::Exact);
+        let find_response = |cassette: &Cassette, request: serde_json::Value| {
+            cassette
+                .find_http_response(&request)
+                .map(|responses| responses[0].message.clone())
+        };
+
+        assert_eq!(
+            find_response(
+                &exact,
+                serde_json::json!({ "query": "query Foo($a: Int) {\n  foo(a: $a)\n}", "operationName": "Foo", "variables": { "a": 2 } })
+            ),
+            Some(serde_json::json!({ "data": { "foo": 2 } }))
+        );
+        assert_eq!(
+            find_response(
+                &exact,
+                serde_json::json!({ "query": "query Foo($a: Int) { foo(a: $a) }", "operationName": "Foo", "variables": { "a": 3 } })
+            ),
+            None
+        );
+
+        let ignore_variables =
+            Cassette::new(captured_messages, ReplayMatchStrictness::IgnoreVariables);
+        let request = serde_json::json!({ "query": "query Foo($a: Int) { foo(a: $a) }", "operationName": "Foo", "variables": { "a": 3 } });
+        assert_eq!(
+            find_response(&ignore_variables, request.clone()),
+            Some(serde_json::json!({ "data": { "foo": 1 } }))
+        );
+        assert_eq!(
+            find_response(&ignore_variables, request.clone()),
+            Some(serde_json::json!({ "data": { "foo": 2 } }))
+        );
+        assert_eq!(
+            find_response(&ignore_variables, request),
+            Some(serde_json::json!({ "data": { "foo": 2 } }))
+        );
+    }
+
+    #[test]
+    fn test_cassette_ws_response_frames() {
+        let frames = [
+            (MessageDirection::Request, serde_json::Value::Null),
+            (MessageDirection::Response, serde_json::Value::Null),
+            (
+                MessageDirection::Request,
+                serde_json::json!({ "id": "a", "type": "subscribe", "payload": { "query": "subscription { foo }" } }),
+            ),
+            (
+                MessageDirection::Response,
+                serde_json::json!({ "id": "a", "type": "next", "payload": { "data": { "foo": 1 } } }),
+            ),
+            (
+                MessageDirection::Response,
+                serde_json::json!({ "id": "b", "type": "next", "payload": { "data": { "bar": 1 } } }),
+            ),
+            (
+                MessageDirection::Response,
+                serde_json::json!({ "id": "a", "type": "complete" }),
+            ),
+        ];
+
+        let captured_messages = frames
+            .into_iter()
+            .enumerate()
+            .map(|(sequence_counter, (message_direction, message))| {
+                create_captured_message(
+                    "ws",
+                    sequence_counter as u64,
+                    ConnectionType::Ws,
+                    message_direction,
+                    message,
+                )
+            })
+            .collect();
+
+        let cassette = Cassette::new(captured_messages, ReplayMatchStrictness::Exact);
+
+        assert_eq!(
+            cassette.find_ws_response_frames(
+                &serde_json::json!({ "query": "subscription {\n  foo\n}" }),
+                &serde_json::json!("1"),
+            ),
+            Some(vec![
+                serde_json::json!({ "id": "1", "type": "next", "payload": { "data": { "foo": 1 } } }),
+                serde_json::json!({ "id": "1", "type": "complete" }),
+            ])
+        );
+        assert_eq!(
+            cassette.find_ws_response_frames(
+                &serde_json::json!({ "query": "subscription { bar }" }),
+                &serde_json::json!("1"),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cassette_http_response_parts() {
+        let request = serde_json::json!({ "query": "{ foo }" });
+        let mut captured_messages = create_http_exchange(
+            "1",
+            request.clone(),
+            serde_json::json!({ "data": { "foo": 1 }, "hasNext": true }),
+        );
+        captured_messages.push(create_captured_message(
+            "1",
+            2,
+            ConnectionType::Http,
+            MessageDirection::Response,
+            serde_json::json!({ "hasNext": false }),
+        ));
+
+        let cassette = Cassette::new(captured_messages, ReplayMatchStrictness::Exact);
+        assert_eq!(
+            cassette
+                .find_http_response(&request)
+                .map(|responses| responses
+                    .into_iter()
+                    .map(|response| response.message)
+                    .collect::<Vec<_>>()),
+            Some(vec![
+                serde_json::json!({ "data": { "foo": 1 }, "hasNext": true }),
+                serde_json::json!({ "hasNext": false }),
+            ])
+        );
+    }
+}
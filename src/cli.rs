@@ -4,7 +4,10 @@ use clap::Parser;
 use graphql_cli_tools::clap_types::{ClapHttpHeaderParser, ClapKeyJsonValueParser};
 use http::{HeaderName, HeaderValue};
 
-use crate::model::inputs::message_filter::{MessageFilter, MessageFilterCliParser};
+use crate::{
+    capture::replay::ReplayMatchStrictness,
+    model::inputs::message_filter::{MessageFilter, MessageFilterCliParser},
+};
 
 #[derive(Debug, Parser)]
 pub struct QueryParams {
@@ -108,12 +111,20 @@ pub struct SubscribeMessagesParams {
 
 #[derive(Debug, Parser)]
 pub struct ServeParams {
+    #[arg(
+        short('c'),
+        long("config"),
+        help("TOML file containing the settings of the proxy, it is re-applied whenever it changes, the command line arguments take precedence over it")
+    )]
+    pub config: Option<PathBuf>,
+
     #[arg(
         short('l'),
         long("listener-address"),
+        required_unless_present("config"),
         help("Address where the proxy server accepts the connections (e.g., 127.0.0.1:8000)")
     )]
-    pub listener_address: String,
+    pub listener_address: Option<String>,
 
     #[arg(
         short('s'),
@@ -154,13 +165,199 @@ pub struct ServeParams {
         help("HTTP header to be sent to the client")
     )]
     pub request_headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        long("message-history-size"),
+        default_value("1000"),
+        help("Number of the most recent messages kept in memory for the admin API, 0 disables the history (default: 1000)")
+    )]
+    pub message_history_size: usize,
+
+    #[arg(
+        long("capture-dir"),
+        help("When set, the proxied messages are written into NDJSON files in this directory")
+    )]
+    pub capture_dir: Option<PathBuf>,
+
+    #[arg(
+        long("capture-max-file-size"),
+        default_value("104857600"),
+        help("Size in bytes after which a new capture file is started (default: 104857600)")
+    )]
+    pub capture_max_file_size: u64,
+
+    #[arg(
+        long("capture-rotation-interval"),
+        default_value("1h"),
+        help("Time after which a new capture file is started (default: 1h)")
+    )]
+    pub capture_rotation_interval: humantime::Duration,
+
+    #[arg(
+        long("capture-queue-size"),
+        default_value("1024"),
+        help("Number of messages waiting to be written to disk, when it is exceeded the messages are dropped (default: 1024)")
+    )]
+    pub capture_queue_size: usize,
+
+    #[arg(
+        long("capture-sensitive-headers"),
+        help("When set, the values of sensitive headers (e.g., authorization, cookie) are written into the capture files instead of being redacted (default: false)")
+    )]
+    pub capture_sensitive_headers: bool,
+
+    #[arg(
+        long("max-upload-size"),
+        default_value("104857600"),
+        help("Size in bytes of the largest multipart request (i.e., file upload) that is accepted, the whole request is buffered in memory (default: 104857600)")
+    )]
+    pub max_upload_size: usize,
+
+    #[arg(
+        long("breakpoint-timeout"),
+        default_value("5m"),
+        help("Time after which a message paused at a breakpoint is resumed, 0s keeps the messages paused until they are resolved (default: 5m)")
+    )]
+    pub breakpoint_timeout: humantime::Duration,
+
+    #[arg(
+        long("replay"),
+        help("Capture file or directory of capture files written by `serve --capture-dir`, when set the responses are served from the recording instead of the server")
+    )]
+    pub replay: Vec<PathBuf>,
+
+    #[arg(
+        value_enum,
+        long("replay-match"),
+        default_value("exact"),
+        help("Defines which parts of the request have to match the recorded request (default: exact)")
+    )]
+    pub replay_match: ReplayMatchStrictness,
+}
+
+#[derive(Debug, Parser)]
+pub struct MockServerParams {
+    #[arg(
+        short('l'),
+        long("listener-address"),
+        help("Address where the mock server accepts the connections (e.g., 127.0.0.1:8001)")
+    )]
+    pub listener_address: String,
+
+    #[arg(long("sdl"), help("Path of the GraphQL schema (SDL) to be mocked"))]
+    pub sdl: PathBuf,
+
+    #[arg(
+        long("seed"),
+        default_value("0"),
+        help("Seed of the generated data, the same seed produces the same responses (default: 0)")
+    )]
+    pub seed: u64,
+
+    #[arg(
+        long("override"),
+        help("JSON file (or directory of JSON files) named after a type, its content is used instead of the generated values of the type")
+    )]
+    pub overrides: Vec<PathBuf>,
+
+    #[arg(
+        long("subscription-interval"),
+        default_value("1s"),
+        help("Time between two events of a subscription (default: 1s)")
+    )]
+    pub subscription_interval: humantime::Duration,
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportHarParams {
+    #[arg(
+        short('i'),
+        long("input"),
+        required(true),
+        help("Capture file or directory of capture files written by `serve --capture-dir`")
+    )]
+    pub inputs: Vec<PathBuf>,
+
+    #[arg(
+        short('o'),
+        long("output"),
+        help("Path of the HAR file to be written (default: standard output)")
+    )]
+    pub output: Option<PathBuf>,
+
+    #[arg(
+        value_enum,
+        short('f'),
+        long("message-filter"),
+        value_parser(MessageFilterCliParser),
+        help("Define a filter for the messages to be exported")
+    )]
+    pub message_filters: Vec<MessageFilter>,
+}
+
+#[derive(Debug, Parser)]
+pub struct StateExportParams {
+    #[arg(
+        short('e'),
+        long("admin-endpoint"),
+        help("Endpoint of the admin API of the proxy (e.g., http://localhost:8000/admin-api/graphql)")
+    )]
+    pub admin_endpoint: String,
+
+    #[arg(
+        long("http-header"),
+        value_parser(ClapHttpHeaderParser),
+        help("HTTP header to be sent to the admin API")
+    )]
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        short('o'),
+        long("output"),
+        help("Path of the snapshot file to be written (default: standard output)")
+    )]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct StateImportParams {
+    #[arg(
+        short('e'),
+        long("admin-endpoint"),
+        help("Endpoint of the admin API of the proxy (e.g., http://localhost:8000/admin-api/graphql)")
+    )]
+    pub admin_endpoint: String,
+
+    #[arg(
+        long("http-header"),
+        value_parser(ClapHttpHeaderParser),
+        help("HTTP header to be sent to the admin API")
+    )]
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+
+    #[arg(
+        short('i'),
+        long("input"),
+        help("Snapshot file written by `state export`")
+    )]
+    pub input: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub enum StateCommand {
+    Export(StateExportParams),
+    Import(StateImportParams),
 }
 
 #[derive(Debug, Parser)]
 pub enum Command {
     Query(QueryParams),
     Serve(ServeParams),
+    MockServer(MockServerParams),
     SubscribeToMessages(SubscribeMessagesParams),
+    ExportHar(ExportHarParams),
+    #[command(subcommand)]
+    State(StateCommand),
     Sdl,
 }
 
@@ -0,0 +1,21 @@
+use crate::{
+    capture::{har::create_har, ndjson_reader::load_captured_messages},
+    cli::ExportHarParams,
+    model::types::message::Message,
+};
+
+pub fn export_har(params: ExportHarParams) -> Result<(), Box<dyn std::error::Error>> {
+    let messages = load_captured_messages(&params.inputs)?
+        .into_iter()
+        .map(Message::from)
+        .collect::<Vec<_>>();
+
+    let har = serde_json::to_string_pretty(&create_har(&messages, &params.message_filters))?;
+
+    match params.output {
+        Some(output) => std::fs::write(output, har)?,
+        None => println!("{har}"),
+    }
+
+    Ok(())
+}
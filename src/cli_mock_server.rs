@@ -0,0 +1,38 @@
+use std::net::ToSocketAddrs;
+
+use axum_helpers::app::AxumApp;
+
+use crate::{
+    cli::MockServerParams,
+    endpoints::router::mock_server_routes,
+    log_location,
+    mock_server::mock_schema::{load_mock_overrides, load_mock_schema, MockSchemaConfig},
+};
+
+pub async fn serve_mock_server(params: MockServerParams) -> Result<(), Box<dyn std::error::Error>> {
+    log::info!("starting application in mock server mode");
+
+    let schema = load_mock_schema(
+        &params.sdl,
+        MockSchemaConfig {
+            seed: params.seed,
+            overrides: load_mock_overrides(&params.overrides)?,
+            subscription_interval: params.subscription_interval.into(),
+        },
+    )?;
+
+    let mut app = AxumApp::new(mock_server_routes(schema));
+
+    for addr in params.listener_address.to_socket_addrs()? {
+        if let Err(e) = app.spawn_server(addr).await {
+            log::error!(
+                "{}, could not listen on address = {addr}, error = {e:?}",
+                log_location!()
+            );
+        }
+    }
+
+    app.join().await;
+
+    Ok(())
+}
@@ -1,9 +1,18 @@
+use std::sync::Arc;
+
 use async_graphql::InputType;
 use graphql_cli_tools::client::{execute, load_variables, ws_request, GraphQlResponse};
+use http::HeaderMap;
+use parking_lot::Mutex;
 
 use crate::{
     cli::{QueryParams, SubscribeMessagesParams},
-    model::enums::{connection_type::ConnectionType, message_direction::MessageDirection},
+    endpoints::graphql_proxy::create_query_params,
+    log_location,
+    model::enums::{
+        connection_type::ConnectionType, http_method::HttpMethod,
+        message_direction::MessageDirection, message_origin::MessageOrigin,
+    },
 };
 
 #[derive(serde::Deserialize)]
@@ -22,6 +31,7 @@ struct MessageSubscriptionHeaders {
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct MessageSubscriptionMessage {
+    event_id: u64,
     message: serde_json::Value,
     connection_id: String,
     sequence_counter: usize,
@@ -29,6 +39,12 @@ struct MessageSubscriptionMessage {
     message_direction: MessageDirection,
     transmitted_headers: Option<MessageSubscriptionHeaders>,
     server_endpoint_url: String,
+    timestamp: String,
+    latency_ms: Option<f64>,
+    origin: MessageOrigin,
+    replayed_event_id: Option<u64>,
+    status_code: Option<u16>,
+    http_method: Option<HttpMethod>,
 }
 
 #[derive(serde::Deserialize)]
@@ -38,6 +54,46 @@ struct MessageSubscriptionResult {
     message: MessageSubscriptionMessage,
 }
 
+fn create_curl_command(message: &MessageSubscriptionMessage) -> Option<String> {
+    let (Some(transmitted_headers), MessageDirection::Request, ConnectionType::Http) = (
+        &message.transmitted_headers,
+        message.message_direction,
+        message.connection_type,
+    ) else {
+        return None;
+    };
+    if message.message.get("files").is_some() {
+        return None;
+    }
+
+    let mut curl_command = match message.http_method.unwrap_or(HttpMethod::Post) {
+        HttpMethod::Get => {
+            let url = reqwest::Url::parse_with_params(
+                &message.server_endpoint_url,
+                &create_query_params(&message.message),
+            )
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| message.server_endpoint_url.clone());
+
+            format!("curl -X GET '{url}'")
+        }
+        HttpMethod::Post => format!(
+            "curl -X POST '{}' -H 'Content-Type: application/json'",
+            message.server_endpoint_url,
+        ),
+    };
+
+    for header in transmitted_headers.all.iter() {
+        curl_command.push_str(&format!(" -H '{}: {}'", header.name, header.value));
+    }
+
+    if message.http_method != Some(HttpMethod::Get) {
+        curl_command.push_str(&format!(" -d '{}'", message.message));
+    }
+
+    Some(curl_command)
+}
+
 fn response_processor(
     response: GraphQlResponse,
     print_curl_command: bool,
@@ -45,30 +101,7 @@ fn response_processor(
     let curl_command = if print_curl_command {
         if let Some(data) = &response.data {
             if let Ok(result) = serde_json::from_value::<MessageSubscriptionResult>(data.clone()) {
-                if let (
-                    Some(transmitted_headers),
-                    MessageDirection::Request,
-                    ConnectionType::Http,
-                ) = (
-                    &result.message.transmitted_headers,
-                    result.message.message_direction,
-                    result.message.connection_type,
-                ) {
-                    let mut curl_command = format!(
-                        "curl -X POST '{}' -H 'Content-Type: application/json'",
-                        result.message.server_endpoint_url,
-                    );
-
-                    for header in transmitted_headers.all.iter() {
-                        curl_command.push_str(&format!(" -H '{}: {}'", header.name, header.value));
-                    }
-
-                    curl_command.push_str(&format!(" -d '{}'", result.message.message));
-
-                    Some(curl_command)
-                } else {
-                    None
-                }
+                create_curl_command(&result.message)
             } else {
                 None
             }
@@ -108,7 +141,7 @@ pub async fn execute_cli_query(params: QueryParams) -> Result<(), Box<dyn std::e
 pub async fn subscribe_to_messages(
     params: SubscribeMessagesParams,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let headers = params.headers.into_iter().collect();
+    let headers: HeaderMap = params.headers.into_iter().collect();
 
     let mut variables = serde_json::Map::<String, serde_json::Value>::new();
     variables.insert(
@@ -126,16 +159,47 @@ pub async fn subscribe_to_messages(
         serde_json::Value::Bool(params.transmitted_headers),
     );
 
-    ws_request(
-        params.server_endpoint,
-        headers,
-        include_str!("graphql_queries/subscribe-to-messages.graphql").to_string(),
-        Option::<&str>::None,
-        variables,
-        |response| response_processor(response, params.as_curl_command),
-        params
-            .try_reconnect_duration
-            .map(|duration| duration.into()),
-    )
-    .await
+    // the reconnection is handled here instead of in ws_request, so the id of the last received
+    // event can be sent on reconnect and no message is lost while the connection is down
+    let last_event_id = Arc::new(Mutex::new(None::<u64>));
+
+    loop {
+        variables.insert(
+            "since".to_string(),
+            serde_json::json!(*last_event_id.lock()),
+        );
+
+        let result = {
+            let last_event_id = last_event_id.clone();
+
+            ws_request(
+                params.server_endpoint.clone(),
+                headers.clone(),
+                include_str!("graphql_queries/subscribe-to-messages.graphql").to_string(),
+                Option::<&str>::None,
+                variables.clone(),
+                move |response| {
+                    if let Some(event_id) = response.data.as_ref().and_then(|data| {
+                        serde_json::from_value::<MessageSubscriptionResult>(data.clone()).ok()
+                    }) {
+                        *last_event_id.lock() = Some(event_id.message.event_id);
+                    }
+
+                    response_processor(response, params.as_curl_command)
+                },
+                None,
+            )
+            .await
+        };
+
+        let Some(try_reconnect_duration) = params.try_reconnect_duration else {
+            return result;
+        };
+
+        if let Err(e) = result {
+            log::error!("{}, {e}", log_location!());
+        }
+
+        tokio::time::sleep(try_reconnect_duration.into()).await;
+    }
 }
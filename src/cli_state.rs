@@ -0,0 +1,84 @@
+use http::HeaderMap;
+
+use crate::{
+    cli::{StateCommand, StateExportParams, StateImportParams},
+    error::AdminApiError,
+};
+
+#[derive(serde::Deserialize)]
+struct AdminApiResponse {
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+}
+
+pub async fn execute_state_command(
+    command: StateCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        StateCommand::Export(params) => export_state(params).await,
+        StateCommand::Import(params) => import_state(params).await,
+    }
+}
+
+async fn export_state(params: StateExportParams) -> Result<(), Box<dyn std::error::Error>> {
+    let data = execute_admin_request(
+        &params.admin_endpoint,
+        params.headers.into_iter().collect(),
+        include_str!("graphql_queries/state-snapshot.graphql"),
+        serde_json::json!({}),
+    )
+    .await?;
+
+    let snapshot = serde_json::to_string_pretty(&data["stateSnapshot"])?;
+
+    match params.output {
+        Some(output) => std::fs::write(output, snapshot)?,
+        None => println!("{snapshot}"),
+    }
+
+    Ok(())
+}
+
+async fn import_state(params: StateImportParams) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot =
+        serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(params.input)?)?;
+
+    execute_admin_request(
+        &params.admin_endpoint,
+        params.headers.into_iter().collect(),
+        include_str!("graphql_queries/restore-state-snapshot.graphql"),
+        serde_json::json!({ "snapshot": snapshot }),
+    )
+    .await?;
+
+    log::info!("state snapshot is restored");
+
+    Ok(())
+}
+
+async fn execute_admin_request(
+    admin_endpoint: &str,
+    headers: HeaderMap,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let response = reqwest::Client::new()
+        .post(admin_endpoint)
+        .headers(headers)
+        .json(&serde_json::json!({ "query": query, "variables": variables }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<AdminApiResponse>()
+        .await?;
+
+    if !response.errors.is_empty() {
+        return Err(AdminApiError {
+            errors: serde_json::Value::Array(response.errors),
+        }
+        .into());
+    }
+
+    Ok(response.data.unwrap_or_default())
+}
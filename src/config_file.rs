@@ -0,0 +1,395 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::{
+    admin_state::AdminState,
+    error::{
+        CannotParseConfigFileError, CannotReadConfigFileError, InvalidConfigHeaderError,
+        LoadConfigFileError,
+    },
+    log_location,
+    model::types::config_changed_event::ConfigChangedEvent,
+};
+
+const CONFIG_FILE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RawConfigFile {
+    listener_address: Option<String>,
+    server_endpoint: Option<String>,
+    server_ws_endpoint: Option<String>,
+    prohibit_mutation: Option<bool>,
+    #[serde(default)]
+    request_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    response_headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxySettings {
+    pub server_graphql_endpoint: Option<String>,
+    pub server_graphql_ws_endpoint: Option<String>,
+    pub prohibit_mutation: Option<bool>,
+    pub request_headers: HeaderMap,
+    pub response_headers: HeaderMap,
+}
+
+impl ProxySettings {
+    // the values of self take precedence, the headers of both are kept and the headers of self
+    // replace the headers of other with the same name
+    pub fn or(&self, other: ProxySettings) -> ProxySettings {
+        let mut request_headers = other.request_headers;
+        request_headers.extend(self.request_headers.clone());
+        let mut response_headers = other.response_headers;
+        response_headers.extend(self.response_headers.clone());
+
+        ProxySettings {
+            server_graphql_endpoint: self
+                .server_graphql_endpoint
+                .clone()
+                .or(other.server_graphql_endpoint),
+            server_graphql_ws_endpoint: self
+                .server_graphql_ws_endpoint
+                .clone()
+                .or(other.server_graphql_ws_endpoint),
+            prohibit_mutation: self.prohibit_mutation.or(other.prohibit_mutation),
+            request_headers,
+            response_headers,
+        }
+    }
+
+    // only the changed settings are applied, so the changes made through the admin api are kept
+    fn apply_changes(&self, previous: &ProxySettings, admin_state: &AdminState) {
+        let is_upstream_changed = self.server_graphql_endpoint != previous.server_graphql_endpoint
+            || self.server_graphql_ws_endpoint != previous.server_graphql_ws_endpoint
+            || self.request_headers != previous.request_headers;
+        if is_upstream_changed {
+            admin_state.override_upstream_settings(|server_endpoints, request_headers| {
+                if self.server_graphql_endpoint != previous.server_graphql_endpoint {
+                    if let Some(server_graphql_endpoint) = &self.server_graphql_endpoint {
+                        server_endpoints.graphql_endpoint = server_graphql_endpoint.clone();
+                    }
+                }
+                if self.server_graphql_ws_endpoint != previous.server_graphql_ws_endpoint {
+                    if let Some(server_graphql_ws_endpoint) = &self.server_graphql_ws_endpoint {
+                        server_endpoints.graphql_ws_endpoint = server_graphql_ws_endpoint.clone();
+                    }
+                }
+
+                apply_header_changes(
+                    request_headers,
+                    &previous.request_headers,
+                    &self.request_headers,
+                );
+            });
+        }
+
+        if self.prohibit_mutation != previous.prohibit_mutation {
+            if let Some(prohibit_mutation) = self.prohibit_mutation {
+                admin_state.set_prohibit_mutation(prohibit_mutation);
+            }
+        }
+
+        apply_header_changes(
+            &mut admin_state.response_headers().write(),
+            &previous.response_headers,
+            &self.response_headers,
+        );
+    }
+}
+
+fn apply_header_changes(headers: &mut HeaderMap, previous: &HeaderMap, current: &HeaderMap) {
+    for name in previous.keys() {
+        if !current.contains_key(name) {
+            headers.remove(name);
+        }
+    }
+
+    for name in current.keys() {
+        if !current
+            .get_all(name)
+            .iter()
+            .eq(previous.get_all(name).iter())
+        {
+            headers.remove(name);
+            for value in current.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    pub listener_address: Option<String>,
+    pub settings: ProxySettings,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, LoadConfigFileError> {
+        let text = std::fs::read_to_string(path).map_err(|e| CannotReadConfigFileError {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+
+        Self::parse(path, &text)
+    }
+
+    fn parse(path: &Path, text: &str) -> Result<Self, LoadConfigFileError> {
+        let raw_config_file =
+            toml::from_str::<RawConfigFile>(text).map_err(|e| CannotParseConfigFileError {
+                path: path.to_path_buf(),
+                source: e,
+            })?;
+
+        Ok(Self {
+            listener_address: raw_config_file.listener_address,
+            settings: ProxySettings {
+                server_graphql_endpoint: raw_config_file.server_endpoint,
+                server_graphql_ws_endpoint: raw_config_file.server_ws_endpoint,
+                prohibit_mutation: raw_config_file.prohibit_mutation,
+                request_headers: parse_headers(path, raw_config_file.request_headers)?,
+                response_headers: parse_headers(path, raw_config_file.response_headers)?,
+            },
+        })
+    }
+}
+
+fn parse_headers(
+    path: &Path,
+    headers: BTreeMap<String, String>,
+) -> Result<HeaderMap, InvalidConfigHeaderError> {
+    headers
+        .into_iter()
+        .map(
+            |(name, value)| match (HeaderName::try_from(&name), HeaderValue::try_from(&value)) {
+                (Ok(name), Ok(value)) => Ok((name, value)),
+                _ => Err(InvalidConfigHeaderError {
+                    path: path.to_path_buf(),
+                    name,
+                }),
+            },
+        )
+        .collect()
+}
+
+// the settings of the command line take precedence over the settings of the file
+pub fn spawn_config_file_watcher(
+    admin_state: AdminState,
+    path: PathBuf,
+    config_file: ConfigFile,
+    command_line_settings: ProxySettings,
+) {
+    std::thread::spawn(move || {
+        let mut current_text = std::fs::read_to_string(&path).ok();
+        let mut current_config_file = config_file;
+
+        loop {
+            std::thread::sleep(CONFIG_FILE_POLL_INTERVAL);
+
+            let text = std::fs::read_to_string(&path).ok();
+            if text.is_none() || text == current_text {
+                continue;
+            }
+            current_text = text;
+
+            let config_file = match ConfigFile::load(&path) {
+                Ok(config_file) => config_file,
+                Err(e) => {
+                    log::error!(
+                        "{}, the configuration file is not applied, error = '{e}'",
+                        log_location!()
+                    );
+                    continue;
+                }
+            };
+            if config_file == current_config_file {
+                continue;
+            }
+
+            if config_file.listener_address != current_config_file.listener_address {
+                log::warn!("the listener address is applied only when the proxy is restarted");
+            }
+
+            command_line_settings
+                .or(config_file.settings.clone())
+                .apply_changes(
+                    &command_line_settings.or(current_config_file.settings.clone()),
+                    &admin_state,
+                );
+            current_config_file = config_file;
+
+            log::info!("configuration file is applied, path = '{}'", path.display());
+
+            let _ = admin_state
+                .config_changed_sender()
+                .send(ConfigChangedEvent {
+                    path: path.display().to_string(),
+                    server_endpoints: admin_state.server_graphql_endpoints_read().clone(),
+                    prohibit_mutation: admin_state.prohibit_mutation(),
+                    timestamp: humantime::format_rfc3339_micros(SystemTime::now()).to_string(),
+                });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_file() {
+        let config_file = ConfigFile::parse(
+            Path::new("proxy.toml"),
+            r#"
+                listener-address = "127.0.0.1:8000"
+                server-endpoint = "http://localhost:8001/api/graphql"
+                prohibit-mutation = true
+
+                [request-headers]
+                authorization = "Bearer foo"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config_file.listener_address.as_deref(),
+            Some("127.0.0.1:8000")
+        );
+        assert_eq!(
+            config_file.settings.server_graphql_endpoint.as_deref(),
+            Some("http://localhost:8001/api/graphql")
+        );
+        assert_eq!(config_file.settings.server_graphql_ws_endpoint, None);
+        assert_eq!(config_file.settings.prohibit_mutation, Some(true));
+        assert_eq!(
+            config_file.settings.request_headers["authorization"],
+            "Bearer foo"
+        );
+        assert!(config_file.settings.response_headers.is_empty());
+
+        assert!(matches!(
+            ConfigFile::parse(Path::new("proxy.toml"), "server-endpoints = \"foo\""),
+            Err(LoadConfigFileError::CannotParse(_))
+        ));
+        assert!(matches!(
+            ConfigFile::parse(
+                Path::new("proxy.toml"),
+                "[response-headers]\n\"x foo\" = \"bar\""
+            ),
+            Err(LoadConfigFileError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_proxy_settings_precedence() {
+        let command_line_settings = ProxySettings {
+            server_graphql_endpoint: Some("http://localhost:8001/api/graphql".to_string()),
+            request_headers: HeaderMap::from_iter([(
+                HeaderName::from_static("x-foo"),
+                HeaderValue::from_static("command-line"),
+            )]),
+            ..Default::default()
+        };
+        let file_settings = ProxySettings {
+            server_graphql_endpoint: Some("http://localhost:8002/api/graphql".to_string()),
+            server_graphql_ws_endpoint: Some("ws://localhost:8002/api/graphql-ws".to_string()),
+            request_headers: HeaderMap::from_iter([
+                (
+                    HeaderName::from_static("x-foo"),
+                    HeaderValue::from_static("file"),
+                ),
+                (
+                    HeaderName::from_static("x-bar"),
+                    HeaderValue::from_static("file"),
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let settings = command_line_settings.or(file_settings);
+
+        assert_eq!(
+            settings.server_graphql_endpoint.as_deref(),
+            Some("http://localhost:8001/api/graphql")
+        );
+        assert_eq!(
+            settings.server_graphql_ws_endpoint.as_deref(),
+            Some("ws://localhost:8002/api/graphql-ws")
+        );
+        assert_eq!(settings.request_headers["x-foo"], "command-line");
+        assert_eq!(settings.request_headers["x-bar"], "file");
+    }
+
+    #[test]
+    fn test_proxy_settings_apply_changes() {
+        let header = |name: &'static str, value: &'static str| {
+            (
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            )
+        };
+
+        let previous_settings = ProxySettings {
+            server_graphql_endpoint: Some("http://localhost:8001/api/graphql".to_string()),
+            request_headers: HeaderMap::from_iter([header("x-foo", "1"), header("x-bar", "1")]),
+            ..Default::default()
+        };
+        let admin_state = AdminState::new(
+            "http://localhost:8002/api/graphql",
+            "ws://localhost:8001/api/graphql-ws",
+            false,
+            HeaderMap::from_iter([
+                header("x-foo", "1"),
+                header("x-bar", "1"),
+                header("x-baz", "admin"),
+            ]),
+            HeaderMap::from_iter([header("x-qux", "admin")]),
+            0,
+        );
+        *admin_state.active_upstream_profile().write() = Some("foo".to_string());
+
+        ProxySettings {
+            prohibit_mutation: Some(true),
+            ..previous_settings.clone()
+        }
+        .apply_changes(&previous_settings, &admin_state);
+        assert_eq!(
+            admin_state.active_upstream_profile().read().as_deref(),
+            Some("foo")
+        );
+
+        ProxySettings {
+            server_graphql_endpoint: Some("http://localhost:8001/api/graphql".to_string()),
+            server_graphql_ws_endpoint: Some("ws://localhost:8003/api/graphql-ws".to_string()),
+            request_headers: HeaderMap::from_iter([header("x-foo", "2")]),
+            ..Default::default()
+        }
+        .apply_changes(&previous_settings, &admin_state);
+
+        let upstream_settings = admin_state.upstream_settings();
+        assert_eq!(
+            upstream_settings.server_endpoints.graphql_endpoint,
+            "http://localhost:8002/api/graphql"
+        );
+        assert_eq!(
+            upstream_settings.server_endpoints.graphql_ws_endpoint,
+            "ws://localhost:8003/api/graphql-ws"
+        );
+        assert_eq!(
+            upstream_settings.request_headers,
+            HeaderMap::from_iter([header("x-foo", "2"), header("x-baz", "admin")])
+        );
+        assert_eq!(
+            *admin_state.response_headers().read(),
+            HeaderMap::from_iter([header("x-qux", "admin")])
+        );
+        assert_eq!(*admin_state.active_upstream_profile().read(), None);
+    }
+}
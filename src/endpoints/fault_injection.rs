@@ -0,0 +1,345 @@
+use std::{
+    sync::atomic::{self, AtomicU64},
+    time::Duration,
+};
+
+use async_graphql::ServerError;
+use axum::extract::ws::{close_code, CloseFrame as AxumCloseFrame, Message as AxumWsMessage};
+
+use crate::{
+    admin_state::AdminState,
+    model::{
+        enums::{connection_type::ConnectionType, operation_type::GraphQLOperationType},
+        types::fault_rule::FaultRule,
+    },
+};
+
+use super::graphql_ws_operation_tracker::WsOperation;
+
+const DEFAULT_FAULT_MESSAGE: &str = "Injected fault";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FaultAction {
+    pub latency: Duration,
+    pub error_message: Option<String>,
+    pub status: Option<u16>,
+    pub truncate_body_bytes: Option<u64>,
+    pub close_after_messages: Option<u64>,
+}
+
+impl FaultAction {
+    // latencies of all the matching rules add up, the first rule wins for the synthetic response
+    // and the smallest limit wins for truncating and closing
+    pub fn from_rules<'a>(rules: impl IntoIterator<Item = &'a FaultRule>) -> Self {
+        rules
+            .into_iter()
+            .fold(FaultAction::default(), |mut action, rule| {
+                action.latency += Duration::from_millis(rule.latency_ms.unwrap_or_default());
+                action.error_message = action.error_message.or(rule.error_message.clone());
+                action.status = action.status.or(rule.status);
+                action.truncate_body_bytes =
+                    min_option(action.truncate_body_bytes, rule.truncate_body_bytes);
+                action.close_after_messages =
+                    min_option(action.close_after_messages, rule.close_after_messages);
+                action
+            })
+    }
+
+    pub fn for_http(
+        admin_state: &AdminState,
+        operation_name: Option<&str>,
+        operation_type: Option<GraphQLOperationType>,
+    ) -> Self {
+        let fault_rules = admin_state.fault_rules().read();
+
+        FaultAction::from_rules(fault_rules.iter().filter(|rule| {
+            rule.is_matching_connection(ConnectionType::Http)
+                && rule.is_matching_operation(operation_name, operation_type)
+                && rule.is_selected_by_percentage()
+        }))
+    }
+
+    pub fn is_synthetic_response(&self) -> bool {
+        self.error_message.is_some() || self.status.is_some()
+    }
+
+    pub fn create_error(&self) -> ServerError {
+        ServerError::new(
+            self.error_message
+                .as_deref()
+                .unwrap_or(DEFAULT_FAULT_MESSAGE),
+            None,
+        )
+    }
+
+    pub fn truncate_text(&self, text: String) -> (String, bool) {
+        match self.truncate_body_bytes {
+            Some(truncate_body_bytes) if (truncate_body_bytes as usize) < text.len() => {
+                let mut end = truncate_body_bytes as usize;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+
+                (text[..end].to_string(), true)
+            }
+            _ => (text, false),
+        }
+    }
+}
+
+fn min_option(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+// the percentage of the rules is evaluated once per connection
+pub struct WsFaultInjector {
+    fault_rules: Vec<FaultRule>,
+    server_message_counters: Vec<AtomicU64>,
+}
+
+impl WsFaultInjector {
+    pub fn new(admin_state: &AdminState) -> Self {
+        let fault_rules = admin_state
+            .fault_rules()
+            .read()
+            .iter()
+            .filter(|rule| {
+                rule.is_matching_connection(ConnectionType::Ws) && rule.is_selected_by_percentage()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Self::from_rules(fault_rules)
+    }
+
+    fn from_rules(fault_rules: Vec<FaultRule>) -> Self {
+        Self {
+            server_message_counters: fault_rules.iter().map(|_| AtomicU64::new(0)).collect(),
+            fault_rules,
+        }
+    }
+
+    pub fn handshake_action(&self) -> FaultAction {
+        FaultAction::from_rules(
+            self.fault_rules
+                .iter()
+                .filter(|rule| rule.is_matching_operation(None, None)),
+        )
+    }
+
+    pub fn server_message_action(&self, operation: Option<&WsOperation>) -> FaultAction {
+        FaultAction::from_rules(
+            self.fault_rules
+                .iter()
+                .filter(|rule| is_rule_matching_ws_operation(rule, operation)),
+        )
+    }
+
+    pub fn apply_to_server_message(
+        &self,
+        message: AxumWsMessage,
+        action: &FaultAction,
+        operation: Option<&WsOperation>,
+    ) -> (AxumWsMessage, bool) {
+        let AxumWsMessage::Text(text) = message else {
+            return (message, false);
+        };
+
+        if action.is_synthetic_response() {
+            if let Some(error_frame) =
+                operation.and_then(|operation| create_error_frame(&text, action, operation))
+            {
+                return (AxumWsMessage::Text(error_frame.to_string()), true);
+            }
+        }
+
+        let (text, is_truncated) = action.truncate_text(text);
+        (AxumWsMessage::Text(text), is_truncated)
+    }
+
+    pub fn is_closing_after_server_message(&self, operation: Option<&WsOperation>) -> bool {
+        let mut is_closing = false;
+        for (rule, server_message_counter) in
+            self.fault_rules.iter().zip(&self.server_message_counters)
+        {
+            let Some(close_after_messages) = rule.close_after_messages else {
+                continue;
+            };
+            if !is_rule_matching_ws_operation(rule, operation) {
+                continue;
+            }
+
+            let server_message_count =
+                server_message_counter.fetch_add(1, atomic::Ordering::SeqCst) + 1;
+            is_closing |= server_message_count >= close_after_messages;
+        }
+
+        is_closing
+    }
+
+    pub fn create_close_frame(&self) -> AxumCloseFrame<'static> {
+        AxumCloseFrame {
+            code: close_code::ERROR,
+            reason: DEFAULT_FAULT_MESSAGE.into(),
+        }
+    }
+}
+
+fn is_rule_matching_ws_operation(rule: &FaultRule, operation: Option<&WsOperation>) -> bool {
+    match operation {
+        Some(operation) => rule.is_matching_operation(
+            operation.operation_name.as_deref(),
+            operation.operation_type,
+        ),
+        None => rule.is_matching_operation(None, None),
+    }
+}
+
+fn create_error_frame(
+    text: &str,
+    action: &FaultAction,
+    operation: &WsOperation,
+) -> Option<serde_json::Value> {
+    let frame = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    if !matches!(
+        frame.get("type").and_then(|ty| ty.as_str()),
+        Some("next" | "data")
+    ) {
+        return None;
+    }
+
+    Some(
+        operation
+            .protocol
+            .create_error_frame(frame.get("id")?, vec![action.create_error()]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        endpoints::graphql_ws_protocol::WsProtocol, model::inputs::fault_rule_input::FaultRuleInput,
+    };
+
+    use super::*;
+
+    fn create_fault_rule_input() -> FaultRuleInput {
+        FaultRuleInput {
+            connection_type: None,
+            operation_name: None,
+            operation_type: None,
+            percentage: 100.0,
+            latency_ms: None,
+            error_message: None,
+            status: None,
+            truncate_body_bytes: None,
+            close_after_messages: None,
+        }
+    }
+
+    #[test]
+    fn test_fault_action_from_rules() {
+        let rules = [
+            FaultRule::new(FaultRuleInput {
+                latency_ms: Some(100),
+                truncate_body_bytes: Some(10),
+                ..create_fault_rule_input()
+            }),
+            FaultRule::new(FaultRuleInput {
+                latency_ms: Some(50),
+                error_message: Some("foo".to_string()),
+                truncate_body_bytes: Some(5),
+                close_after_messages: Some(3),
+                ..create_fault_rule_input()
+            }),
+            FaultRule::new(FaultRuleInput {
+                error_message: Some("bar".to_string()),
+                status: Some(503),
+                ..create_fault_rule_input()
+            }),
+        ];
+
+        assert_eq!(
+            FaultAction::from_rules(&rules),
+            FaultAction {
+                latency: Duration::from_millis(150),
+                error_message: Some("foo".to_string()),
+                status: Some(503),
+                truncate_body_bytes: Some(5),
+                close_after_messages: Some(3),
+            }
+        );
+        assert_eq!(FaultAction::from_rules(&[]), FaultAction::default());
+    }
+
+    #[test]
+    fn test_fault_action_truncate_text() {
+        let action = FaultAction {
+            truncate_body_bytes: Some(2),
+            ..FaultAction::default()
+        };
+
+        assert_eq!(
+            action.truncate_text("foo".to_string()),
+            ("fo".to_string(), true)
+        );
+        assert_eq!(
+            action.truncate_text("fo".to_string()),
+            ("fo".to_string(), false)
+        );
+        assert_eq!(
+            action.truncate_text("äö".to_string()),
+            ("ä".to_string(), true)
+        );
+        assert_eq!(
+            FaultAction::default().truncate_text("foo".to_string()),
+            ("foo".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn test_ws_fault_injector() {
+        let fault_injector = WsFaultInjector::from_rules(vec![FaultRule::new(FaultRuleInput {
+            operation_type: Some(GraphQLOperationType::Subscription),
+            error_message: Some("foo".to_string()),
+            close_after_messages: Some(2),
+            ..create_fault_rule_input()
+        })]);
+        let operation = WsOperation {
+            protocol: WsProtocol::GraphQLTransportWs,
+            operation_name: None,
+            operation_type: Some(GraphQLOperationType::Subscription),
+        };
+
+        assert_eq!(fault_injector.handshake_action(), FaultAction::default());
+
+        let action = fault_injector.server_message_action(None);
+        assert_eq!(action, FaultAction::default());
+        assert!(!fault_injector.is_closing_after_server_message(None));
+        assert!(!fault_injector.is_closing_after_server_message(None));
+
+        let message = AxumWsMessage::Text(
+            serde_json::json!({ "id": "1", "type": "next", "payload": {} }).to_string(),
+        );
+        let action = fault_injector.server_message_action(Some(&operation));
+        assert_eq!(
+            fault_injector.apply_to_server_message(message, &action, Some(&operation)),
+            (
+                AxumWsMessage::Text(
+                    serde_json::json!({
+                        "id": "1",
+                        "type": "error",
+                        "payload": [{ "message": "foo" }],
+                    })
+                    .to_string()
+                ),
+                true
+            )
+        );
+        assert!(!fault_injector.is_closing_after_server_message(Some(&operation)));
+        assert!(fault_injector.is_closing_after_server_message(Some(&operation)));
+    }
+}
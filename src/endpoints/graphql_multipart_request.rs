@@ -0,0 +1,213 @@
+use axum::http::HeaderValue;
+use bytes::Bytes;
+
+use crate::error::{
+    CannotParseMultipartFieldError, MissingMultipartBoundaryError, MissingMultipartFieldError,
+    ParseMultipartRequestError,
+};
+
+use super::multipart::{media_type, multipart_boundary, MultipartPart, MultipartParts};
+
+const OPERATIONS_FIELD_NAME: &str = "operations";
+const MAP_FIELD_NAME: &str = "map";
+
+#[derive(Debug)]
+pub struct GraphQLMultipartRequest {
+    content_type: HeaderValue,
+    boundary: String,
+    body: Bytes,
+    parts: Vec<MultipartPart>,
+}
+
+impl GraphQLMultipartRequest {
+    pub fn is_multipart_request(content_type: Option<&HeaderValue>) -> bool {
+        content_type
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| media_type(content_type) == "multipart/form-data")
+    }
+
+    pub fn parse(
+        content_type: HeaderValue,
+        body: Bytes,
+    ) -> Result<Self, ParseMultipartRequestError> {
+        let boundary = content_type
+            .to_str()
+            .ok()
+            .and_then(multipart_boundary)
+            .ok_or(MissingMultipartBoundaryError)?;
+        let parts = MultipartParts::new(&boundary).push(&body);
+
+        let request = Self {
+            content_type,
+            boundary,
+            body,
+            parts,
+        };
+        request.field(OPERATIONS_FIELD_NAME)?;
+        request.field(MAP_FIELD_NAME)?;
+
+        Ok(request)
+    }
+
+    pub fn content_type(&self) -> &HeaderValue {
+        &self.content_type
+    }
+
+    pub fn body(&self) -> Bytes {
+        self.body.clone()
+    }
+
+    pub fn graphql_request(&self) -> Result<async_graphql::Request, ParseMultipartRequestError> {
+        Ok(
+            serde_json::from_slice(&self.field(OPERATIONS_FIELD_NAME)?.body).map_err(|e| {
+                CannotParseMultipartFieldError {
+                    field: OPERATIONS_FIELD_NAME,
+                    source: e,
+                }
+            })?,
+        )
+    }
+
+    // the content of the files is not captured, only their metadata
+    pub fn captured_message(&self, graphql_request: &async_graphql::Request) -> serde_json::Value {
+        let map = self
+            .field(MAP_FIELD_NAME)
+            .ok()
+            .and_then(|part| serde_json::from_slice::<serde_json::Value>(&part.body).ok())
+            .unwrap_or_default();
+
+        let files = self
+            .parts
+            .iter()
+            .filter_map(|part| {
+                let name = part.disposition_parameter("name")?;
+                if name == OPERATIONS_FIELD_NAME || name == MAP_FIELD_NAME {
+                    return None;
+                }
+
+                Some(serde_json::json!({
+                    "name": name,
+                    "fileName": part.disposition_parameter("filename"),
+                    "contentType": part.header("content-type"),
+                    "size": part.body.len(),
+                    "paths": map.get(&name),
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        let mut message = serde_json::json!(graphql_request);
+        if let Some(message) = message.as_object_mut() {
+            message.insert("files".to_string(), serde_json::Value::Array(files));
+        }
+
+        message
+    }
+
+    pub fn set_graphql_request(&mut self, graphql_request: &async_graphql::Request) {
+        let operations = serde_json::to_vec(graphql_request).unwrap_or_default();
+        for part in self.parts.iter_mut() {
+            if part.disposition_parameter("name").as_deref() == Some(OPERATIONS_FIELD_NAME) {
+                part.body = operations.clone();
+            }
+        }
+
+        let mut body = Vec::new();
+        for part in &self.parts {
+            body.extend_from_slice(
+                format!("--{}\r\n{}\r\n\r\n", self.boundary, part.headers).as_bytes(),
+            );
+            body.extend_from_slice(&part.body);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+
+        self.body = Bytes::from(body);
+    }
+
+    fn field(&self, name: &'static str) -> Result<&MultipartPart, MissingMultipartFieldError> {
+        self.parts
+            .iter()
+            .find(|part| part.disposition_parameter("name").as_deref() == Some(name))
+            .ok_or(MissingMultipartFieldError { field: name })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_multipart_request() -> GraphQLMultipartRequest {
+        GraphQLMultipartRequest::parse(
+            HeaderValue::from_static("multipart/form-data; boundary=foo"),
+            Bytes::from_static(
+                b"--foo\r\n\
+                Content-Disposition: form-data; name=\"operations\"\r\n\r\n\
+                {\"query\":\"mutation ($file: Upload!) { upload(file: $file) }\",\"variables\":{\"file\":null}}\r\n\
+                --foo\r\n\
+                Content-Disposition: form-data; name=\"map\"\r\n\r\n\
+                {\"0\":[\"variables.file\"]}\r\n\
+                --foo\r\n\
+                Content-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\n\
+                Content-Type: text/plain\r\n\r\n\
+                bar\r\n\
+                --foo--\r\n",
+            ),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_is_multipart_request() {
+        assert!(GraphQLMultipartRequest::is_multipart_request(Some(
+            &HeaderValue::from_static("multipart/form-data; boundary=foo")
+        )));
+        assert!(!GraphQLMultipartRequest::is_multipart_request(Some(
+            &HeaderValue::from_static("application/json")
+        )));
+        assert!(!GraphQLMultipartRequest::is_multipart_request(None));
+    }
+
+    #[test]
+    fn test_captured_message() {
+        let multipart_request = create_multipart_request();
+        let graphql_request = multipart_request.graphql_request().unwrap();
+
+        assert_eq!(
+            graphql_request.query,
+            "mutation ($file: Upload!) { upload(file: $file) }"
+        );
+        assert_eq!(
+            multipart_request.captured_message(&graphql_request)["files"],
+            serde_json::json!([{
+                "name": "0",
+                "fileName": "a.txt",
+                "contentType": "text/plain",
+                "size": 3,
+                "paths": ["variables.file"],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_set_graphql_request() {
+        let mut multipart_request = create_multipart_request();
+        let mut graphql_request = multipart_request.graphql_request().unwrap();
+        graphql_request.query = "mutation ($file: Upload!) { foo(file: $file) }".to_string();
+
+        multipart_request.set_graphql_request(&graphql_request);
+        let multipart_request = GraphQLMultipartRequest::parse(
+            multipart_request.content_type().clone(),
+            multipart_request.body(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            multipart_request.graphql_request().unwrap().query,
+            "mutation ($file: Upload!) { foo(file: $file) }"
+        );
+        assert_eq!(
+            multipart_request.captured_message(&graphql_request)["files"][0]["size"],
+            serde_json::json!(3)
+        );
+    }
+}
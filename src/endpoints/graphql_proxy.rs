@@ -1,34 +1,256 @@
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
 
-use async_graphql::{Response, ServerError};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use async_graphql_parser::{
     parse_query,
     types::{DocumentOperations, ExecutableDocument, OperationType},
 };
-use axum::{body::Body, http::HeaderMap, response::IntoResponse};
-use tokio::sync::broadcast;
+use axum::{
+    body::{Body, Bytes},
+    extract::FromRequest,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::IntoResponse,
+};
+use futures_util::StreamExt;
 
 use crate::{
     admin_state::ConnectionId,
     app_state::AppState,
+    breakpoints::{BreakpointResolution, BreakpointTarget, Breakpoints},
+    capture::{captured_message::payload_to_text, replay::Cassette},
+    error::ProxyError,
     log_location,
+    message_sender::MessageSender,
     model::{
-        enums::{connection_type::ConnectionType, message_direction::MessageDirection},
-        types::{headers::Headers, message::Message},
+        enums::{
+            connection_type::ConnectionType, http_method::HttpMethod,
+            message_direction::MessageDirection, message_origin::MessageOrigin,
+            operation_type::GraphQLOperationType,
+        },
+        types::{headers::Headers, message::Message, operation_stub::OperationStub},
     },
     utils::move_and_replace_headers,
 };
 
+use super::{
+    fault_injection::FaultAction,
+    graphql_multipart_request::GraphQLMultipartRequest,
+    multipart::media_type,
+    response_body_capture::{frame_captured_texts, ResponseBodyCapture},
+};
+
+const GRAPHQL_RESPONSE_JSON_MEDIA_TYPE: &str = "application/graphql-response+json";
+
 pub async fn post_graphql_proxy(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    request: axum::extract::Request,
+) -> Result<axum::response::Response, GraphQLResponse> {
+    let headers = request.headers().clone();
+
+    if GraphQLMultipartRequest::is_multipart_request(headers.get(http::header::CONTENT_TYPE)) {
+        let parsed_request =
+            parse_multipart_request(&headers, request.into_body(), state.max_upload_size()).await;
+        let (graphql_request, multipart_request) = match parsed_request {
+            Ok(parsed_request) => parsed_request,
+            Err(error) => {
+                return Ok(respond_to_unreadable_request(
+                    &state,
+                    &headers,
+                    HttpMethod::Post,
+                    error,
+                )
+                .into_response());
+            }
+        };
+
+        return proxy_graphql_request(
+            &state,
+            headers,
+            graphql_request,
+            RequestEncoding::Multipart(multipart_request),
+            None,
+        )
+        .await
+        .map(IntoResponse::into_response);
+    }
+
+    let graphql_request = match GraphQLRequest::from_request(request, &state).await {
+        Ok(graphql_request) => graphql_request,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    proxy_graphql_request(
+        &state,
+        headers,
+        graphql_request.0,
+        RequestEncoding::Json,
+        None,
+    )
+    .await
+    .map(IntoResponse::into_response)
+}
+
+fn parse_get_params(params: GraphQLGetParams) -> Result<async_graphql::Request, ProxyError> {
+    let mut graphql_request = serde_json::Map::new();
+    graphql_request.insert(
+        "query".to_string(),
+        serde_json::Value::from(params.query.unwrap_or_default()),
+    );
+    if let Some(operation_name) = params.operation_name {
+        graphql_request.insert(
+            "operationName".to_string(),
+            serde_json::Value::from(operation_name),
+        );
+    }
+    for (name, param) in [
+        ("variables", params.variables),
+        ("extensions", params.extensions),
+    ] {
+        let Some(param) = param else {
+            continue;
+        };
+
+        let value = serde_json::from_str::<serde_json::Value>(&param).map_err(|e| {
+            ProxyError::parse(
+                log_location!(),
+                format!("the '{name}' parameter is not valid json, error = '{e}'"),
+            )
+        })?;
+        graphql_request.insert(name.to_string(), value);
+    }
+
+    serde_json::from_value::<async_graphql::Request>(serde_json::Value::Object(graphql_request))
+        .map_err(|e| ProxyError::parse(log_location!(), e))
+}
+
+fn respond_to_unreadable_request(
+    state: &AppState,
+    headers: &HeaderMap,
+    http_method: HttpMethod,
+    error: ProxyError,
+) -> (StatusCode, HeaderMap, Body) {
+    let server_endpoint_url = state
+        .admin_state()
+        .upstream_settings()
+        .server_endpoints
+        .graphql_endpoint;
+    let message_capture = HttpMessageCapture::new(
+        ConnectionId::new(),
+        state.admin_state().message_sender_ref().clone(),
+        Arc::new(server_endpoint_url),
+        http_method,
+        None,
+    );
+
+    respond_with_proxy_error(
+        &message_capture,
+        error,
+        response_media_type(headers),
+        state.admin_state().response_headers().read().clone(),
+    )
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLGetParams {
+    query: Option<String>,
+    operation_name: Option<String>,
+    variables: Option<String>,
+    extensions: Option<String>,
+}
+
+// get requests are forwarded as get requests, so the responses of the server stay cacheable
+pub async fn get_graphql_proxy(
     axum::extract::State(state): axum::extract::State<AppState>,
     headers: HeaderMap,
-    graphql_request: GraphQLRequest,
-) -> Result<impl IntoResponse, GraphQLResponse> {
+    axum::extract::Query(params): axum::extract::Query<GraphQLGetParams>,
+) -> Result<(StatusCode, HeaderMap, Body), GraphQLResponse> {
+    let graphql_request = match parse_get_params(params) {
+        Ok(graphql_request) => graphql_request,
+        Err(error) => {
+            return Ok(respond_to_unreadable_request(
+                &state,
+                &headers,
+                HttpMethod::Get,
+                error,
+            ));
+        }
+    };
+
+    proxy_graphql_request(
+        &state,
+        headers,
+        graphql_request,
+        RequestEncoding::QueryString,
+        None,
+    )
+    .await
+}
+
+async fn parse_multipart_request(
+    headers: &HeaderMap,
+    body: Body,
+    max_upload_size: usize,
+) -> Result<(async_graphql::Request, GraphQLMultipartRequest), ProxyError> {
+    let content_type = headers
+        .get(http::header::CONTENT_TYPE)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_static("multipart/form-data"));
+
+    let mut chunks = body.into_data_stream();
+    let mut body = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| ProxyError::parse(log_location!(), e))?;
+        if body.len() + chunk.len() > max_upload_size {
+            return Err(ProxyError::PayloadTooLarge {
+                location: log_location!(),
+                max_size: max_upload_size,
+            });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = Bytes::from(body);
+
+    let multipart_request = GraphQLMultipartRequest::parse(content_type, body)
+        .map_err(|e| ProxyError::parse(log_location!(), e))?;
+    let graphql_request = multipart_request
+        .graphql_request()
+        .map_err(|e| ProxyError::parse(log_location!(), e))?;
+
+    Ok((graphql_request, multipart_request))
+}
+
+pub enum RequestEncoding {
+    Json,
+    Multipart(GraphQLMultipartRequest),
+    QueryString,
+}
+
+impl RequestEncoding {
+    pub fn http_method(&self) -> HttpMethod {
+        match self {
+            Self::Json | Self::Multipart(_) => HttpMethod::Post,
+            Self::QueryString => HttpMethod::Get,
+        }
+    }
+}
+
+pub async fn proxy_graphql_request(
+    state: &AppState,
+    headers: HeaderMap,
+    mut graphql_request: async_graphql::Request,
+    mut request_encoding: RequestEncoding,
+    replayed_event_id: Option<u64>,
+) -> Result<(StatusCode, HeaderMap, Body), GraphQLResponse> {
     log::debug!("GaphQL request headers = {:?}", headers);
 
-    let connection_id = ConnectionId::new();
-    let mut sequence_counter = 0;
+    let response_media_type = response_media_type(&headers);
 
     const PROHIBITED_HEADER_NAMES_TO_SERVER: &[&str] = &["host", "content-length", "content-type"];
 
@@ -43,82 +265,334 @@ pub async fn post_graphql_proxy(
         })
         .collect();
 
-    let mut additional_request_headers = state.admin_state().request_headers().read().clone();
-    move_and_replace_headers(&mut request_headers, &mut additional_request_headers, &[]);
+    let mut upstream_settings = state.admin_state().upstream_settings();
+    move_and_replace_headers(
+        &mut request_headers,
+        &mut upstream_settings.request_headers,
+        &[],
+    );
 
-    let server_endpoint_url = Arc::new(
-        state
-            .admin_state()
-            .server_graphql_endpoints_read()
-            .graphql_endpoint
-            .clone(),
+    let server_endpoint_url = Arc::new(upstream_settings.server_endpoints.graphql_endpoint);
+    let server_client = state.server_client(upstream_settings.accept_invalid_certificates);
+
+    let message_capture = HttpMessageCapture::new(
+        ConnectionId::new(),
+        state.admin_state().message_sender_ref().clone(),
+        server_endpoint_url.clone(),
+        request_encoding.http_method(),
+        replayed_event_id,
+    );
+    message_capture.send_message_to_subscriptions(
+        captured_request(&graphql_request, &request_encoding),
+        MessageDirection::Request,
+        &request_headers,
+        if replayed_event_id.is_some() {
+            MessageOrigin::Replay
+        } else {
+            MessageOrigin::Client
+        },
+        None,
     );
 
-    let message_sender = state.admin_state().message_sender_ref().clone();
-    if message_sender.receiver_count() != 0 {
-        let _ = message_sender.send(Message {
-            connection_id: connection_id.as_arc_string(),
-            message: Arc::new(serde_json::json!(graphql_request.0)),
-            sequence_counter,
-            connection_type: ConnectionType::Http,
-            message_direction: MessageDirection::Request,
-            transmitted_headers: Some(Arc::new(Headers::from_header_map(request_headers.clone()))),
-            server_endpoint_url: server_endpoint_url.clone(),
-        });
-    }
-    sequence_counter += 1;
+    let respond_with_parse_error = |error| {
+        respond_with_proxy_error(
+            &message_capture,
+            error,
+            response_media_type.clone(),
+            state.admin_state().response_headers().read().clone(),
+        )
+    };
 
-    let parsed_graphql_query = parse_query(&graphql_request.0.query)
-        .inspect_err(|e| log::error!("{}, {}", log_location!(), e.to_string()))
-        .map_err(|e| {
-            GraphQLResponse::from(Response::from_errors(vec![ServerError::new(
-                e.to_string(),
+    let (mut parsed_graphql_query, mut operation_name, mut operation_type) =
+        match parse_graphql_request(&graphql_request) {
+            Ok(parsed_graphql_request) => parsed_graphql_request,
+            Err(e) => {
+                return Ok(respond_with_parse_error(ProxyError::parse(
+                    log_location!(),
+                    e,
+                )))
+            }
+        };
+
+    let breakpoint_target = |operation_name: &Option<String>, operation_type| BreakpointTarget {
+        connection_id: message_capture.connection_id.as_arc_string(),
+        connection_type: ConnectionType::Http,
+        operation_name: operation_name.clone(),
+        operation_type,
+    };
+    match state
+        .admin_state()
+        .breakpoints()
+        .pause_if_matching(
+            &breakpoint_target(&operation_name, operation_type),
+            MessageDirection::Request,
+            captured_request(&graphql_request, &request_encoding),
+        )
+        .await
+    {
+        BreakpointResolution::Resume => (),
+        BreakpointResolution::Edit(edited_request) => {
+            graphql_request = match serde_json::from_value(edited_request.clone()) {
+                Ok(graphql_request) => graphql_request,
+                Err(e) => {
+                    return Ok(respond_with_parse_error(ProxyError::parse(
+                        log_location!(),
+                        e,
+                    )));
+                }
+            };
+            if let RequestEncoding::Multipart(multipart_request) = &mut request_encoding {
+                multipart_request.set_graphql_request(&graphql_request);
+            }
+            message_capture.send_message_to_subscriptions(
+                edited_request,
+                MessageDirection::Request,
+                &request_headers,
+                MessageOrigin::Breakpoint,
                 None,
-            )]))
-        })?;
+            );
+
+            (parsed_graphql_query, operation_name, operation_type) =
+                match parse_graphql_request(&graphql_request) {
+                    Ok(parsed_graphql_request) => parsed_graphql_request,
+                    Err(e) => {
+                        return Ok(respond_with_parse_error(ProxyError::parse(
+                            log_location!(),
+                            e,
+                        )));
+                    }
+                };
+        }
+        BreakpointResolution::Drop => {
+            return Err(
+                message_capture.capture_proxy_error(ProxyError::policy_rejection(
+                    log_location!(),
+                    "The request was dropped at a breakpoint",
+                )),
+            );
+        }
+    }
+    let breakpoint_target = breakpoint_target(&operation_name, operation_type);
+
+    let additional_response_headers = state.admin_state().response_headers().read().clone();
+
+    // without an operation name, a document is rejected when any of its operations is a mutation
+    if matches!(request_encoding, RequestEncoding::QueryString)
+        && parsed_graphql_query
+            .clone()
+            .is_some_and(|parsed_graphql_query| {
+                is_query_of_type(
+                    graphql_request.operation_name.as_ref(),
+                    parsed_graphql_query,
+                    OperationType::Mutation,
+                )
+            })
+    {
+        return Ok(reject_get_mutation(
+            &message_capture,
+            response_media_type,
+            additional_response_headers,
+        ));
+    }
 
     if state.admin_state().prohibit_mutation()
-        && is_query_of_type(
-            graphql_request.0.operation_name.as_ref(),
-            parsed_graphql_query,
-            OperationType::Mutation,
-        )
+        && parsed_graphql_query.is_some_and(|parsed_graphql_query| {
+            is_query_of_type(
+                graphql_request.operation_name.as_ref(),
+                parsed_graphql_query,
+                OperationType::Mutation,
+            )
+        })
     {
-        return Err(GraphQLResponse::from(Response::from_errors(vec![
-            ServerError::new("Mutations are set to be prohibited", None),
-        ])));
+        return Err(
+            message_capture.capture_proxy_error(ProxyError::policy_rejection(
+                log_location!(),
+                "Mutations are set to be prohibited",
+            )),
+        );
     }
 
-    let server_response = state
-        .server_client()
-        .post(server_endpoint_url.as_ref())
-        .headers(request_headers)
-        .json(&graphql_request.0)
-        .send()
-        .await
-        .inspect_err(|e| log::error!("{}, {}", log_location!(), e.to_string()))
-        .map_err(|e| {
-            GraphQLResponse::from(Response::from_errors(vec![ServerError::new(
-                e.to_string(),
-                None,
-            )]))
-        })?;
+    let fault_action = FaultAction::for_http(
+        state.admin_state(),
+        operation_name.as_deref(),
+        operation_type,
+    );
+    tokio::time::sleep(fault_action.latency).await;
+    if fault_action.is_synthetic_response() {
+        return Ok(respond_with_fault(
+            &message_capture,
+            &fault_action,
+            response_media_type,
+            additional_response_headers,
+        ));
+    }
+
+    let operation_stub = operation_name.and_then(|operation_name| {
+        state
+            .admin_state()
+            .operation_stubs()
+            .read()
+            .get(&operation_name)
+            .cloned()
+    });
+    if let Some(operation_stub) = operation_stub {
+        return Ok(respond_with_operation_stub(
+            &message_capture,
+            operation_stub,
+            response_media_type,
+            additional_response_headers,
+        )
+        .await);
+    }
+
+    if let Some(cassette) = state.cassette() {
+        let graphql_request = serde_json::json!(graphql_request);
+        let (status, headers, texts) =
+            find_recorded_response(cassette, &graphql_request, additional_response_headers)
+                .ok_or_else(|| {
+                    log::debug!("no recorded response matches the request = {graphql_request}");
+                    message_capture.capture_proxy_error(ProxyError::MissingRecordedResponse {
+                        location: log_location!(),
+                    })
+                })?;
+
+        for text in texts.iter() {
+            message_capture.send_message_to_subscriptions(
+                parse_response_text(text),
+                MessageDirection::Response,
+                &headers,
+                MessageOrigin::Cassette,
+                Some(status),
+            );
+        }
+
+        let text = frame_captured_texts(
+            headers
+                .get(http::header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok()),
+            texts.iter().map(String::as_str),
+        );
+        return Ok((status, headers, Body::from(text)));
+    }
+
+    let server_request = match &request_encoding {
+        RequestEncoding::Json => server_client
+            .post(server_endpoint_url.as_ref())
+            .json(&graphql_request),
+        RequestEncoding::Multipart(multipart_request) => server_client
+            .post(server_endpoint_url.as_ref())
+            .header(
+                http::header::CONTENT_TYPE,
+                multipart_request.content_type().clone(),
+            )
+            .body(multipart_request.body()),
+        RequestEncoding::QueryString => server_client
+            .get(server_endpoint_url.as_ref())
+            .query(&create_query_params(&serde_json::json!(graphql_request))),
+    }
+    .headers(request_headers);
+
+    let server_response = match server_request.send().await {
+        Ok(server_response) => server_response,
+        Err(e) => {
+            return Ok(respond_with_proxy_error(
+                &message_capture,
+                ProxyError::from_reqwest_error(log_location!(), e),
+                response_media_type,
+                additional_response_headers,
+            ));
+        }
+    };
 
     log::debug!("Server response = {:?}", server_response);
 
-    let additional_response_headers = state.admin_state().response_headers().read().clone();
     process_server_response(
-        connection_id,
-        sequence_counter,
-        message_sender,
+        &message_capture,
         server_response,
+        response_media_type,
         additional_response_headers,
-        server_endpoint_url,
+        &fault_action,
+        state.admin_state().breakpoints(),
+        &breakpoint_target,
     )
     .await
 }
 
-fn is_query_of_type(
+fn captured_request(
+    graphql_request: &async_graphql::Request,
+    request_encoding: &RequestEncoding,
+) -> serde_json::Value {
+    match request_encoding {
+        RequestEncoding::Multipart(multipart_request) => {
+            multipart_request.captured_message(graphql_request)
+        }
+        RequestEncoding::Json | RequestEncoding::QueryString => {
+            serde_json::json!(graphql_request)
+        }
+    }
+}
+
+pub fn create_query_params(graphql_request: &serde_json::Value) -> Vec<(String, String)> {
+    let serde_json::Value::Object(graphql_request) = graphql_request else {
+        return Vec::new();
+    };
+
+    ["query", "operationName", "variables", "extensions"]
+        .into_iter()
+        .filter_map(|name| match graphql_request.get(name)? {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(value) if value.is_empty() => None,
+            serde_json::Value::Object(value) if value.is_empty() => None,
+            serde_json::Value::String(value) => Some((name.to_string(), value.clone())),
+            value => Some((name.to_string(), value.to_string())),
+        })
+        .collect()
+}
+
+// see the graphql over http spec
+fn response_media_type(headers: &HeaderMap) -> HeaderValue {
+    let accepts_graphql_response_json = headers
+        .get_all(http::header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_range| media_type(media_range) == GRAPHQL_RESPONSE_JSON_MEDIA_TYPE);
+
+    if accepts_graphql_response_json {
+        HeaderValue::from_static(GRAPHQL_RESPONSE_JSON_MEDIA_TYPE)
+    } else {
+        HeaderValue::from_static("application/json")
+    }
+}
+
+// the query of a persisted query may be missing, such requests are forwarded without being parsed
+fn parse_graphql_request(
+    graphql_request: &async_graphql::Request,
+) -> Result<
+    (
+        Option<ExecutableDocument>,
+        Option<String>,
+        Option<GraphQLOperationType>,
+    ),
+    async_graphql_parser::Error,
+> {
+    if graphql_request.query.is_empty() {
+        return Ok((None, graphql_request.operation_name.clone(), None));
+    }
+
+    let parsed_graphql_query = parse_query(&graphql_request.query)?;
+
+    let operation_name = graphql_request
+        .operation_name
+        .clone()
+        .or_else(|| single_operation_name(&parsed_graphql_query));
+    let operation_type = find_operation_type(operation_name.as_deref(), &parsed_graphql_query)
+        .map(GraphQLOperationType::from);
+
+    Ok((Some(parsed_graphql_query), operation_name, operation_type))
+}
+
+pub fn is_query_of_type(
     operation_name: Option<impl AsRef<str>>,
     parsed_graphql_query: ExecutableDocument,
     query_type: OperationType,
@@ -141,16 +615,44 @@ fn is_query_of_type(
     }
 }
 
+pub fn find_operation_type(
+    operation_name: Option<&str>,
+    parsed_graphql_query: &ExecutableDocument,
+) -> Option<OperationType> {
+    match &parsed_graphql_query.operations {
+        DocumentOperations::Single(operation) => Some(operation.node.ty),
+        DocumentOperations::Multiple(operations) => operation_name
+            .and_then(|operation_name| operations.get(operation_name))
+            .map(|operation| operation.node.ty),
+    }
+}
+
+fn single_operation_name(parsed_graphql_query: &ExecutableDocument) -> Option<String> {
+    match &parsed_graphql_query.operations {
+        DocumentOperations::Single(_operation) => None,
+        DocumentOperations::Multiple(operations) => {
+            if operations.len() == 1 {
+                operations.keys().next().map(|name| name.to_string())
+            } else {
+                None
+            }
+        }
+    }
+}
+
 async fn process_server_response(
-    connection_id: ConnectionId,
-    sequence_counter: u64,
-    message_sender: broadcast::Sender<Message>,
+    message_capture: &HttpMessageCapture,
     mut server_response: reqwest::Response,
-    mut additional_response_headers: HeaderMap,
-    server_endpoint_url: Arc<String>,
-) -> Result<impl IntoResponse, GraphQLResponse> {
+    response_media_type: HeaderValue,
+    additional_response_headers: HeaderMap,
+    fault_action: &FaultAction,
+    breakpoints: &Breakpoints,
+    breakpoint_target: &BreakpointTarget,
+) -> Result<(StatusCode, HeaderMap, Body), GraphQLResponse> {
     const PROHIBITED_HEADER_NAMES_TO_CLIENT: &[&str] = &[];
 
+    let status = server_response.status();
+
     let mut headers = HeaderMap::new();
     move_and_replace_headers(
         &mut headers,
@@ -158,42 +660,392 @@ async fn process_server_response(
         PROHIBITED_HEADER_NAMES_TO_CLIENT,
     );
 
-    move_and_replace_headers(&mut headers, &mut additional_response_headers, &[]);
+    move_and_replace_headers(&mut headers, &mut additional_response_headers.clone(), &[]);
 
-    let text = server_response.text().await.map_err(|e| {
-        log::error!("{}, {}", log_location!(), e.to_string());
-        GraphQLResponse::from(Response::from_errors(vec![ServerError::new(
-            e.to_string(),
-            None,
-        )]))
-    })?;
-
-    if message_sender.receiver_count() != 0 {
-        let transmitted_headers = Some(Arc::new(Headers::from_header_map(headers.clone())));
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-            let _ = message_sender.send(Message {
-                connection_id: connection_id.as_arc_string(),
-                message: Arc::new(json),
-                sequence_counter,
-                connection_type: ConnectionType::Http,
-                message_direction: MessageDirection::Response,
-                transmitted_headers,
-                server_endpoint_url,
-            });
+    // a streamed body (e.g., a subscription) may never end, so it is never buffered
+    let is_buffered = fault_action.truncate_body_bytes.is_some()
+        || breakpoints.is_matching(breakpoint_target, MessageDirection::Response);
+    let is_streamed = headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| {
+            let content_type = content_type.trim_start().to_ascii_lowercase();
+            content_type.starts_with("multipart/") || content_type.starts_with("text/event-stream")
+        });
+    if is_buffered && is_streamed {
+        log::warn!(
+            "the response truncation and breakpoints are not applied to a streamed response, url = {}",
+            message_capture.server_endpoint_url
+        );
+    }
+    if !is_buffered || is_streamed {
+        let body =
+            stream_server_response(message_capture.clone(), server_response, status, &headers);
+        return Ok((status, headers, body));
+    }
+
+    let body = match server_response.bytes().await {
+        Ok(body) => body,
+        Err(e) => {
+            return Ok(respond_with_proxy_error(
+                message_capture,
+                ProxyError::from_reqwest_error(log_location!(), e),
+                response_media_type,
+                additional_response_headers,
+            ));
+        }
+    };
+
+    let (text, is_truncated) =
+        fault_action.truncate_text(String::from_utf8_lossy(&body).into_owned());
+    if is_truncated {
+        headers.remove(http::header::CONTENT_LENGTH);
+    }
+
+    let response = parse_response_text(&text);
+    message_capture.send_message_to_subscriptions(
+        response.clone(),
+        MessageDirection::Response,
+        &headers,
+        if is_truncated {
+            MessageOrigin::Fault
         } else {
-            let _ = message_sender.send(Message {
-                connection_id: connection_id.as_arc_string(),
-                message: Arc::new(serde_json::Value::from(text.clone())),
-                sequence_counter,
-                connection_type: ConnectionType::Http,
-                message_direction: MessageDirection::Response,
-                transmitted_headers,
-                server_endpoint_url,
-            });
+            MessageOrigin::Server
+        },
+        Some(status),
+    );
+
+    let body = match breakpoints
+        .pause_if_matching(breakpoint_target, MessageDirection::Response, response)
+        .await
+    {
+        BreakpointResolution::Resume if is_truncated => Body::from(text),
+        BreakpointResolution::Resume => Body::from(body),
+        BreakpointResolution::Edit(edited_response) => {
+            headers.remove(http::header::CONTENT_LENGTH);
+            message_capture.send_message_to_subscriptions(
+                edited_response.clone(),
+                MessageDirection::Response,
+                &headers,
+                MessageOrigin::Breakpoint,
+                Some(status),
+            );
+
+            Body::from(match edited_response {
+                serde_json::Value::String(text) => text,
+                edited_response => edited_response.to_string(),
+            })
+        }
+        BreakpointResolution::Drop => {
+            return Err(
+                message_capture.capture_proxy_error(ProxyError::policy_rejection(
+                    log_location!(),
+                    "The response was dropped at a breakpoint",
+                )),
+            );
+        }
+    };
+
+    Ok((status, headers, body))
+}
+
+fn respond_with_proxy_error(
+    message_capture: &HttpMessageCapture,
+    error: ProxyError,
+    response_media_type: HeaderValue,
+    mut additional_response_headers: HeaderMap,
+) -> (StatusCode, HeaderMap, Body) {
+    log::error!("{}", error.message());
+
+    let status = match error {
+        ProxyError::Parse { .. } if response_media_type == GRAPHQL_RESPONSE_JSON_MEDIA_TYPE => {
+            StatusCode::BAD_REQUEST
         }
+        ref error => error.status(),
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(http::header::CONTENT_TYPE, response_media_type);
+    move_and_replace_headers(&mut headers, &mut additional_response_headers, &[]);
+
+    let response = serde_json::json!({
+        "errors": [error.to_server_error()],
+    });
+    let text = response.to_string();
+
+    message_capture.send_message_to_subscriptions(
+        response,
+        MessageDirection::Response,
+        &headers,
+        MessageOrigin::Proxy,
+        Some(status),
+    );
+
+    (status, headers, Body::from(text))
+}
+
+fn stream_server_response(
+    message_capture: HttpMessageCapture,
+    server_response: reqwest::Response,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Body {
+    let headers = headers.clone();
+    let mut body_capture = ResponseBodyCapture::from_content_type(
+        headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok()),
+    );
+    let mut chunks = server_response.bytes_stream();
+
+    Body::from_stream(async_stream::stream! {
+        while let Some(chunk) = chunks.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    for text in body_capture.push(&chunk) {
+                        message_capture.send_message_to_subscriptions(
+                            parse_response_text(&text),
+                            MessageDirection::Response,
+                            &headers,
+                            MessageOrigin::Server,
+                            Some(status),
+                        );
+                    }
+
+                    yield Ok(chunk);
+                }
+                Err(e) => {
+                    log::error!("{}, {}", log_location!(), e.to_string());
+                    yield Err(e);
+                    break;
+                }
+            }
+        }
+
+        for text in body_capture.finish() {
+            message_capture.send_message_to_subscriptions(
+                parse_response_text(&text),
+                MessageDirection::Response,
+                &headers,
+                MessageOrigin::Server,
+                Some(status),
+            );
+        }
+    })
+}
+
+fn reject_get_mutation(
+    message_capture: &HttpMessageCapture,
+    response_media_type: HeaderValue,
+    mut additional_response_headers: HeaderMap,
+) -> (StatusCode, HeaderMap, Body) {
+    let error = ProxyError::policy_rejection(log_location!(), "Mutations cannot be sent over GET");
+    log::error!("{}", error.message());
+
+    let status = StatusCode::METHOD_NOT_ALLOWED;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(http::header::CONTENT_TYPE, response_media_type);
+    headers.insert(http::header::ALLOW, HeaderValue::from_static("POST"));
+    move_and_replace_headers(&mut headers, &mut additional_response_headers, &[]);
+
+    let response = serde_json::json!({
+        "errors": [error.to_server_error()],
+    });
+    let text = response.to_string();
+
+    message_capture.send_message_to_subscriptions(
+        response,
+        MessageDirection::Response,
+        &headers,
+        MessageOrigin::Proxy,
+        Some(status),
+    );
+
+    (status, headers, Body::from(text))
+}
+
+fn respond_with_fault(
+    message_capture: &HttpMessageCapture,
+    fault_action: &FaultAction,
+    response_media_type: HeaderValue,
+    mut additional_response_headers: HeaderMap,
+) -> (StatusCode, HeaderMap, Body) {
+    log::debug!("responding with an injected fault = {:?}", fault_action);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(http::header::CONTENT_TYPE, response_media_type);
+    move_and_replace_headers(&mut headers, &mut additional_response_headers, &[]);
+
+    let status = fault_action
+        .status
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::OK);
+
+    let response = serde_json::json!({ "errors": [fault_action.create_error()] });
+    let text = response.to_string();
+
+    message_capture.send_message_to_subscriptions(
+        response,
+        MessageDirection::Response,
+        &headers,
+        MessageOrigin::Fault,
+        Some(status),
+    );
+
+    (status, headers, Body::from(text))
+}
+
+async fn respond_with_operation_stub(
+    message_capture: &HttpMessageCapture,
+    operation_stub: OperationStub,
+    response_media_type: HeaderValue,
+    mut additional_response_headers: HeaderMap,
+) -> (StatusCode, HeaderMap, Body) {
+    log::debug!(
+        "responding with the stub of operation = '{}'",
+        operation_stub.operation_name
+    );
+
+    tokio::time::sleep(Duration::from_millis(operation_stub.delay_ms)).await;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(http::header::CONTENT_TYPE, response_media_type);
+    move_and_replace_headers(&mut headers, &mut additional_response_headers, &[]);
+
+    let status = StatusCode::from_u16(operation_stub.status).unwrap_or(StatusCode::OK);
+    let text = operation_stub.response.to_string();
+
+    message_capture.send_message_to_subscriptions(
+        operation_stub.response,
+        MessageDirection::Response,
+        &headers,
+        MessageOrigin::Stub,
+        Some(status),
+    );
+
+    (status, headers, Body::from(text))
+}
+
+fn find_recorded_response(
+    cassette: &Cassette,
+    graphql_request: &serde_json::Value,
+    mut additional_response_headers: HeaderMap,
+) -> Option<(StatusCode, HeaderMap, Vec<String>)> {
+    const PROHIBITED_HEADER_NAMES_TO_CLIENT: &[&str] = &["content-length", "transfer-encoding"];
+
+    let recorded_responses = cassette.find_http_response(graphql_request)?;
+    let recorded_response = recorded_responses.first()?;
+
+    let mut headers = HeaderMap::new();
+    move_and_replace_headers(
+        &mut headers,
+        &mut recorded_response.header_map().unwrap_or_default(),
+        PROHIBITED_HEADER_NAMES_TO_CLIENT,
+    );
+    move_and_replace_headers(&mut headers, &mut additional_response_headers, &[]);
+
+    let status = recorded_response
+        .status_code
+        .and_then(|status_code| StatusCode::from_u16(status_code).ok())
+        .unwrap_or(StatusCode::OK);
+
+    Some((
+        status,
+        headers,
+        recorded_responses
+            .iter()
+            .map(|recorded_response| payload_to_text(&recorded_response.message))
+            .collect(),
+    ))
+}
+
+fn parse_response_text(text: &str) -> serde_json::Value {
+    serde_json::from_str::<serde_json::Value>(text)
+        .unwrap_or_else(|_| serde_json::Value::from(text))
+}
+
+#[derive(Clone)]
+struct HttpMessageCapture {
+    connection_id: ConnectionId,
+    sequence_counter: Arc<AtomicU64>,
+    request_timestamp: SystemTime,
+    message_sender: MessageSender,
+    server_endpoint_url: Arc<String>,
+    http_method: HttpMethod,
+    replayed_event_id: Option<u64>,
+}
+
+impl HttpMessageCapture {
+    fn new(
+        connection_id: ConnectionId,
+        message_sender: MessageSender,
+        server_endpoint_url: Arc<String>,
+        http_method: HttpMethod,
+        replayed_event_id: Option<u64>,
+    ) -> Self {
+        Self {
+            connection_id,
+            sequence_counter: Arc::new(AtomicU64::new(0)),
+            request_timestamp: SystemTime::now(),
+            message_sender,
+            server_endpoint_url,
+            http_method,
+            replayed_event_id,
+        }
+    }
+
+    fn send_message_to_subscriptions(
+        &self,
+        message: serde_json::Value,
+        message_direction: MessageDirection,
+        headers: &HeaderMap,
+        origin: MessageOrigin,
+        status: Option<StatusCode>,
+    ) {
+        let sequence_counter = self.sequence_counter.fetch_add(1, atomic::Ordering::SeqCst);
+        if !self.message_sender.has_listeners() {
+            return;
+        }
+
+        let timestamp = SystemTime::now();
+        let latency = match message_direction {
+            MessageDirection::Request => None,
+            MessageDirection::Response => timestamp.duration_since(self.request_timestamp).ok(),
+        };
+
+        self.message_sender.send(Message {
+            event_id: 0,
+            connection_id: self.connection_id.as_arc_string(),
+            message: Arc::new(message),
+            sequence_counter,
+            connection_type: ConnectionType::Http,
+            message_direction,
+            transmitted_headers: Some(Arc::new(Headers::from_header_map(headers.clone()))),
+            server_endpoint_url: self.server_endpoint_url.clone(),
+            timestamp,
+            latency,
+            origin,
+            replayed_event_id: self.replayed_event_id,
+            status_code: status.map(|status| status.as_u16()),
+            http_method: match message_direction {
+                MessageDirection::Request => Some(self.http_method),
+                MessageDirection::Response => None,
+            },
+        });
     }
 
-    Ok((headers, Body::from(text)))
+    fn capture_proxy_error(&self, error: ProxyError) -> GraphQLResponse {
+        self.send_message_to_subscriptions(
+            serde_json::json!({ "errors": [error.to_server_error()] }),
+            MessageDirection::Response,
+            &HeaderMap::new(),
+            MessageOrigin::Proxy,
+            Some(StatusCode::OK),
+        );
+
+        GraphQLResponse::from(error)
+    }
 }
 
 fn create_curl_command_string(
@@ -217,3 +1069,47 @@ fn create_curl_command_string(
 
     Ok(curl_command)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_query_params() {
+        let graphql_request = serde_json::from_value::<async_graphql::Request>(serde_json::json!({
+            "query": "query Foo($a: Int) { foo(a: $a) }",
+            "operationName": "Foo",
+            "variables": { "a": 1 },
+        }))
+        .unwrap();
+
+        assert_eq!(
+            create_query_params(&serde_json::json!(graphql_request)),
+            vec![
+                (
+                    "query".to_string(),
+                    "query Foo($a: Int) { foo(a: $a) }".to_string()
+                ),
+                ("operationName".to_string(), "Foo".to_string()),
+                ("variables".to_string(), r#"{"a":1}"#.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_response_media_type() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(response_media_type(&headers), "application/json");
+
+        headers.insert(
+            http::header::ACCEPT,
+            HeaderValue::from_static(
+                "application/graphql-response+json;q=1, application/json;q=0.9",
+            ),
+        );
+        assert_eq!(
+            response_media_type(&headers),
+            GRAPHQL_RESPONSE_JSON_MEDIA_TYPE
+        );
+    }
+}
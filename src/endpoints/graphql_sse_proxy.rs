@@ -0,0 +1,450 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use async_graphql_axum::GraphQLRequest;
+use async_graphql_parser::{parse_query, types::OperationType};
+use axum::{
+    http::{HeaderMap, HeaderValue},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, Message as TungsteniteMessage},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::{
+    admin_state::ConnectionId,
+    app_state::AppState,
+    capture::replay::Cassette,
+    error::{ProxyError, ProxyStage},
+    log_location,
+    model::{
+        enums::{message_direction::MessageDirection, message_origin::MessageOrigin},
+        types::{connection_init_rule::ConnectionInitRule, headers::Headers},
+    },
+    utils::move_and_replace_headers,
+};
+
+use super::{
+    graphql_proxy::is_query_of_type, graphql_ws_protocol::WsProtocol,
+    graphql_ws_proxy::WsMessageCapture,
+};
+
+const CONNECTION_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const PROHIBITED_HEADER_NAMES_TO_SERVER: &[&str] = &[
+    "host",
+    "content-length",
+    "content-type",
+    "accept",
+    "connection",
+    "upgrade",
+    "sec-websocket-key",
+    "sec-websocket-version",
+    "sec-websocket-protocol",
+];
+
+const OPERATION_ID: &str = "1";
+
+// the "distinct connections" mode of graphql-sse, the ws session to the server is not a proxied
+// connection, so fault rules and breakpoints do not apply to it
+pub async fn post_graphql_sse_proxy(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    mut headers: HeaderMap,
+    graphql_request: GraphQLRequest,
+) -> Result<axum::response::Response, ProxyError> {
+    let graphql_request = graphql_request.0;
+
+    let mut upstream_settings = state.admin_state().upstream_settings();
+    let server_endpoint_url = Arc::new(upstream_settings.server_endpoints.graphql_ws_endpoint);
+
+    log::debug!(
+        "Starting sse stream with ws endpoint: '{}'",
+        server_endpoint_url
+    );
+
+    log::debug!("GraphQL SSE request headers = {:?}", headers);
+
+    if state.admin_state().prohibit_mutation() {
+        if let Ok(parsed_graphql_query) = parse_query(&graphql_request.query) {
+            if is_query_of_type(
+                graphql_request.operation_name.as_ref(),
+                parsed_graphql_query,
+                OperationType::Mutation,
+            ) {
+                return Err(ProxyError::policy_rejection(
+                    log_location!(),
+                    "Mutations are set to be prohibited",
+                ));
+            }
+        }
+    }
+
+    if let Some(cassette) = state.cassette() {
+        return replay_sse_operation(
+            headers,
+            &state,
+            cassette,
+            serde_json::json!(graphql_request),
+            server_endpoint_url,
+        );
+    }
+
+    let mut request = server_endpoint_url
+        .as_ref()
+        .into_client_request()
+        .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))?;
+
+    move_and_replace_headers(
+        request.headers_mut(),
+        &mut headers,
+        PROHIBITED_HEADER_NAMES_TO_SERVER,
+    );
+    request.headers_mut().insert(
+        http::header::SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static("graphql-transport-ws, graphql-ws"),
+    );
+
+    move_and_replace_headers(
+        request.headers_mut(),
+        &mut upstream_settings.request_headers,
+        &[],
+    );
+
+    let message_capture = WsMessageCapture::new(
+        ConnectionId::new(),
+        state.admin_state().message_sender_ref().clone(),
+        server_endpoint_url,
+    );
+    message_capture.send_message_to_subscriptions(
+        serde_json::json!(graphql_request),
+        MessageDirection::Request,
+        Some(Arc::new(Headers::from_header_map(
+            request.headers().clone(),
+        ))),
+        MessageOrigin::Client,
+    );
+
+    let (mut server_stream, server_response) = tokio_tungstenite::connect_async_tls_with_config(
+        request,
+        None,
+        false,
+        Some(state.ws_connector(upstream_settings.accept_invalid_certificates)),
+    )
+    .await
+    .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))?;
+
+    log::debug!("Websocket server response = {:?}", server_response);
+
+    message_capture.send_message_to_subscriptions(
+        serde_json::Value::Null,
+        MessageDirection::Response,
+        Some(Arc::new(Headers::from_header_map(
+            server_response.headers().clone(),
+        ))),
+        MessageOrigin::Server,
+    );
+
+    let server_protocol = server_response
+        .headers()
+        .get(http::header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|subprotocol| WsProtocol::from_subprotocol(subprotocol.to_str().ok()?.trim()))
+        .unwrap_or(WsProtocol::GraphQLTransportWs);
+
+    let mut connection_init_payload = serde_json::Map::new();
+    ConnectionInitRule::apply_all(
+        &state.admin_state().connection_init_rules().read(),
+        &mut connection_init_payload,
+    );
+
+    start_operation(
+        &mut server_stream,
+        &message_capture,
+        server_protocol,
+        serde_json::Value::Object(connection_init_payload),
+        serde_json::json!(graphql_request),
+    )
+    .await?;
+
+    let events = async_stream::stream! {
+        let mut is_operation_finished = false;
+        while let Some(frame) = receive_frame(&mut server_stream, &message_capture).await {
+            if frame.get("type").and_then(|ty| ty.as_str()) == Some("ping") {
+                if send_frame(
+                    &mut server_stream,
+                    &message_capture,
+                    serde_json::json!({ "type": "pong" }),
+                )
+                .await
+                .is_err()
+                {
+                    break;
+                }
+
+                continue;
+            }
+
+            let (event, is_finished) = create_event(&frame);
+            if let Some(event) = event {
+                yield Ok::<_, Infallible>(event);
+            }
+            if is_finished {
+                is_operation_finished = true;
+                break;
+            }
+        }
+
+        if !is_operation_finished {
+            let error = ProxyError::upstream(
+                log_location!(),
+                "the connection to the server is closed before the operation is completed",
+            );
+            let payload = serde_json::json!({ "errors": [error.to_server_error()] });
+            yield Ok(Event::default().event("next").data(payload.to_string()));
+        }
+        yield Ok(Event::default().event("complete").data(""));
+
+        let _ = server_stream.close(None).await;
+        log::debug!("sse stream is completed");
+    };
+
+    Ok(create_sse_response(&state, events))
+}
+
+fn replay_sse_operation(
+    mut headers: HeaderMap,
+    state: &AppState,
+    cassette: &Cassette,
+    graphql_request: serde_json::Value,
+    server_endpoint_url: Arc<String>,
+) -> Result<axum::response::Response, ProxyError> {
+    let mut request_headers = HeaderMap::new();
+    move_and_replace_headers(
+        &mut request_headers,
+        &mut headers,
+        PROHIBITED_HEADER_NAMES_TO_SERVER,
+    );
+
+    let mut additional_request_headers = state.admin_state().request_headers().read().clone();
+    move_and_replace_headers(&mut request_headers, &mut additional_request_headers, &[]);
+
+    let message_capture = WsMessageCapture::new(
+        ConnectionId::new(),
+        state.admin_state().message_sender_ref().clone(),
+        server_endpoint_url,
+    );
+    message_capture.send_message_to_subscriptions(
+        graphql_request.clone(),
+        MessageDirection::Request,
+        Some(Arc::new(Headers::from_header_map(request_headers))),
+        MessageOrigin::Client,
+    );
+
+    let frames = cassette
+        .find_ws_response_frames(&graphql_request, &serde_json::Value::from(OPERATION_ID))
+        .ok_or_else(|| {
+            log::debug!("no recorded response matches the request = {graphql_request}");
+            ProxyError::MissingRecordedResponse {
+                location: log_location!(),
+            }
+        })?;
+
+    let mut events = Vec::new();
+    for frame in frames {
+        message_capture.send_message_to_subscriptions(
+            frame.clone(),
+            MessageDirection::Response,
+            None,
+            MessageOrigin::Cassette,
+        );
+
+        let (event, is_finished) = create_event(&frame);
+        events.extend(event.map(Ok::<_, Infallible>));
+        if is_finished {
+            break;
+        }
+    }
+    events.push(Ok(Event::default().event("complete").data("")));
+
+    Ok(create_sse_response(
+        state,
+        futures_util::stream::iter(events),
+    ))
+}
+
+fn create_sse_response(
+    state: &AppState,
+    events: impl futures_util::Stream<Item = Result<Event, Infallible>> + Send + 'static,
+) -> axum::response::Response {
+    let mut response = Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response();
+
+    let mut additional_response_headers = state.admin_state().response_headers().read().clone();
+    move_and_replace_headers(
+        response.headers_mut(),
+        &mut additional_response_headers,
+        &[],
+    );
+
+    response
+}
+
+fn create_event(frame: &serde_json::Value) -> (Option<Event>, bool) {
+    match frame.get("type").and_then(|ty| ty.as_str()) {
+        Some("next" | "data") => {
+            let payload = frame.get("payload").cloned().unwrap_or_default();
+            (
+                Some(Event::default().event("next").data(payload.to_string())),
+                false,
+            )
+        }
+        Some("error" | "connection_error") => {
+            let errors = match frame.get("payload").cloned().unwrap_or_default() {
+                serde_json::Value::Array(errors) => errors,
+                error => vec![error],
+            };
+            let payload = serde_json::json!({ "errors": errors });
+            (
+                Some(Event::default().event("next").data(payload.to_string())),
+                true,
+            )
+        }
+        Some("complete") => (None, true),
+        _ => (None, false),
+    }
+}
+
+async fn start_operation(
+    server_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    message_capture: &WsMessageCapture,
+    server_protocol: WsProtocol,
+    connection_init_payload: serde_json::Value,
+    operation_payload: serde_json::Value,
+) -> Result<(), ProxyError> {
+    send_frame(
+        server_stream,
+        message_capture,
+        serde_json::json!({ "type": "connection_init", "payload": connection_init_payload }),
+    )
+    .await
+    .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))?;
+
+    tokio::time::timeout(
+        CONNECTION_ACK_TIMEOUT,
+        wait_for_connection_ack(server_stream, message_capture),
+    )
+    .await
+    .map_err(|e| ProxyError::UpstreamTimeout {
+        location: log_location!(),
+        stage: ProxyStage::UpstreamConnect,
+        source: e.into(),
+    })??;
+
+    send_frame(
+        server_stream,
+        message_capture,
+        serde_json::json!({
+            "id": OPERATION_ID,
+            "type": match server_protocol {
+                WsProtocol::GraphQLTransportWs => "subscribe",
+                WsProtocol::SubscriptionsTransportWs => "start",
+            },
+            "payload": operation_payload,
+        }),
+    )
+    .await
+    .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))
+}
+
+async fn wait_for_connection_ack(
+    server_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    message_capture: &WsMessageCapture,
+) -> Result<(), ProxyError> {
+    while let Some(frame) = receive_frame(server_stream, message_capture).await {
+        match frame.get("type").and_then(|ty| ty.as_str()) {
+            Some("connection_ack") => return Ok(()),
+            Some("ping") => send_frame(
+                server_stream,
+                message_capture,
+                serde_json::json!({ "type": "pong" }),
+            )
+            .await
+            .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))?,
+            Some("connection_error") => {
+                return Err(ProxyError::upstream(
+                    log_location!(),
+                    format!("the server rejected the connection, frame = {frame}"),
+                ))
+            }
+            _ => (),
+        }
+    }
+
+    Err(ProxyError::upstream(
+        log_location!(),
+        "the connection to the server is closed",
+    ))
+}
+
+async fn receive_frame(
+    server_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    message_capture: &WsMessageCapture,
+) -> Option<serde_json::Value> {
+    loop {
+        match server_stream.next().await? {
+            Ok(TungsteniteMessage::Text(text)) => {
+                let Ok(frame) = serde_json::from_str::<serde_json::Value>(&text) else {
+                    log::error!(
+                        "{}, the frame is not json, frame = '{text}'",
+                        log_location!()
+                    );
+                    continue;
+                };
+                message_capture.send_message_to_subscriptions(
+                    frame.clone(),
+                    MessageDirection::Response,
+                    None,
+                    MessageOrigin::Server,
+                );
+
+                return Some(frame);
+            }
+            Ok(TungsteniteMessage::Close(close_frame)) => {
+                log::debug!(
+                    "connection to the server is closed, close frame = {:?}",
+                    close_frame
+                );
+                return None;
+            }
+            Ok(_) => (),
+            Err(e) => {
+                log::error!(
+                    "{}, error reading from server, error = '{e}'",
+                    log_location!()
+                );
+                return None;
+            }
+        }
+    }
+}
+
+async fn send_frame(
+    server_stream: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    message_capture: &WsMessageCapture,
+    frame: serde_json::Value,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let text = frame.to_string();
+    message_capture.send_message_to_subscriptions(
+        frame,
+        MessageDirection::Request,
+        None,
+        MessageOrigin::Proxy,
+    );
+
+    server_stream.send(TungsteniteMessage::Text(text)).await
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use async_graphql_parser::parse_query;
+use axum::extract::ws::Message as AxumWsMessage;
+use parking_lot::Mutex;
+
+use crate::model::enums::operation_type::GraphQLOperationType;
+
+use super::{
+    graphql_proxy::find_operation_type,
+    graphql_ws_protocol::{OperationFrame, WsProtocol},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WsOperation {
+    pub protocol: WsProtocol,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+}
+
+impl WsOperation {
+    pub fn from_client_message(message: &AxumWsMessage) -> Option<(String, Self)> {
+        let operation_frame = OperationFrame::from_json(&parse_text_message(message)?)?;
+
+        let operation_type = parse_query(&operation_frame.query)
+            .ok()
+            .and_then(|parsed_graphql_query| {
+                find_operation_type(
+                    operation_frame.operation_name.as_deref(),
+                    &parsed_graphql_query,
+                )
+            })
+            .map(GraphQLOperationType::from);
+
+        Some((
+            frame_id_to_string(&operation_frame.id),
+            Self {
+                protocol: operation_frame.protocol,
+                operation_name: operation_frame.operation_name,
+                operation_type,
+            },
+        ))
+    }
+}
+
+pub struct WsOperationTracker {
+    operations: Mutex<HashMap<String, WsOperation>>,
+}
+
+impl WsOperationTracker {
+    pub fn new() -> Self {
+        Self {
+            operations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn track_client_message(&self, message: &AxumWsMessage) {
+        if let Some((id, operation)) = WsOperation::from_client_message(message) {
+            self.operations.lock().insert(id, operation);
+        }
+    }
+
+    pub fn find_server_message_operation(&self, message: &AxumWsMessage) -> Option<WsOperation> {
+        let frame = parse_text_message(message)?;
+        let id = frame_id_to_string(frame.get("id")?);
+
+        self.operations.lock().get(&id).cloned()
+    }
+}
+
+fn parse_text_message(message: &AxumWsMessage) -> Option<serde_json::Value> {
+    match message {
+        AxumWsMessage::Text(text) => serde_json::from_str::<serde_json::Value>(text).ok(),
+        _ => None,
+    }
+}
+
+fn frame_id_to_string(id: &serde_json::Value) -> String {
+    match id {
+        serde_json::Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_operation_tracker() {
+        let operation_tracker = WsOperationTracker::new();
+        operation_tracker.track_client_message(&AxumWsMessage::Text(
+            serde_json::json!({
+                "id": 1,
+                "type": "start",
+                "payload": { "query": "subscription Foo { foo }", "operationName": "Foo" },
+            })
+            .to_string(),
+        ));
+        operation_tracker.track_client_message(&AxumWsMessage::Text(
+            serde_json::json!({ "type": "connection_init" }).to_string(),
+        ));
+
+        assert_eq!(
+            operation_tracker.find_server_message_operation(&AxumWsMessage::Text(
+                serde_json::json!({ "id": 1, "type": "data", "payload": {} }).to_string()
+            )),
+            Some(WsOperation {
+                protocol: WsProtocol::SubscriptionsTransportWs,
+                operation_name: Some("Foo".to_string()),
+                operation_type: Some(GraphQLOperationType::Subscription),
+            })
+        );
+        assert_eq!(
+            operation_tracker.find_server_message_operation(&AxumWsMessage::Text(
+                serde_json::json!({ "id": 2, "type": "data", "payload": {} }).to_string()
+            )),
+            None
+        );
+        assert_eq!(
+            operation_tracker.find_server_message_operation(&AxumWsMessage::Text(
+                serde_json::json!({ "type": "connection_ack" }).to_string()
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use async_graphql::ServerError;
+use axum::extract::ws::{close_code, CloseFrame as AxumCloseFrame};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WsProtocol {
+    GraphQLTransportWs,
+    SubscriptionsTransportWs,
+}
+
+impl WsProtocol {
+    pub fn from_subprotocol(subprotocol: &str) -> Option<Self> {
+        match subprotocol {
+            "graphql-transport-ws" => Some(Self::GraphQLTransportWs),
+            "graphql-ws" => Some(Self::SubscriptionsTransportWs),
+            _ => None,
+        }
+    }
+
+    pub fn subprotocol(&self) -> &'static str {
+        match self {
+            Self::GraphQLTransportWs => "graphql-transport-ws",
+            Self::SubscriptionsTransportWs => "graphql-ws",
+        }
+    }
+
+    pub fn other(&self) -> Self {
+        match self {
+            Self::GraphQLTransportWs => Self::SubscriptionsTransportWs,
+            Self::SubscriptionsTransportWs => Self::GraphQLTransportWs,
+        }
+    }
+
+    pub fn from_operation_frame_type(frame_type: &str) -> Option<Self> {
+        match frame_type {
+            "subscribe" => Some(Self::GraphQLTransportWs),
+            "start" => Some(Self::SubscriptionsTransportWs),
+            _ => None,
+        }
+    }
+
+    pub fn create_error_frame(
+        &self,
+        id: &serde_json::Value,
+        errors: Vec<ServerError>,
+    ) -> serde_json::Value {
+        match self {
+            Self::GraphQLTransportWs => serde_json::json!({
+                "id": id,
+                "type": "error",
+                "payload": errors,
+            }),
+            Self::SubscriptionsTransportWs => {
+                // subscriptions-transport-ws sends a single error object as payload
+                let payload = errors
+                    .into_iter()
+                    .next()
+                    .map(|error| serde_json::json!(error))
+                    .unwrap_or(serde_json::Value::Null);
+
+                serde_json::json!({
+                    "id": id,
+                    "type": "error",
+                    "payload": payload,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct TranslatedFrame {
+    pub forwarded_frame: Option<serde_json::Value>,
+    pub reply_frame: Option<serde_json::Value>,
+    pub close_frame: Option<AxumCloseFrame<'static>>,
+}
+
+impl TranslatedFrame {
+    fn forward(frame: serde_json::Value) -> Self {
+        Self {
+            forwarded_frame: Some(frame),
+            ..Self::default()
+        }
+    }
+
+    fn close(code: u16, reason: &'static str) -> Self {
+        Self {
+            close_frame: Some(AxumCloseFrame {
+                code,
+                reason: reason.into(),
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+// graphql-transport-ws closes the socket with 4403 when the connection_init frame is rejected
+const FORBIDDEN_CLOSE_CODE: u16 = 4403;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct WsProtocolTranslation {
+    pub client_protocol: WsProtocol,
+    pub server_protocol: WsProtocol,
+}
+
+impl WsProtocolTranslation {
+    pub fn new(client_protocol: WsProtocol, server_protocol: WsProtocol) -> Option<Self> {
+        (client_protocol != server_protocol).then_some(Self {
+            client_protocol,
+            server_protocol,
+        })
+    }
+
+    pub fn translate_client_frame(&self, frame: serde_json::Value) -> TranslatedFrame {
+        let frame_type = frame_type(&frame);
+
+        match (self.client_protocol, frame_type.as_deref()) {
+            (WsProtocol::GraphQLTransportWs, Some("subscribe")) => {
+                TranslatedFrame::forward(with_frame_type(frame, "start"))
+            }
+            (WsProtocol::GraphQLTransportWs, Some("complete")) => {
+                TranslatedFrame::forward(with_frame_type(frame, "stop"))
+            }
+            (WsProtocol::GraphQLTransportWs, Some("ping")) => TranslatedFrame {
+                reply_frame: Some(serde_json::json!({ "type": "pong" })),
+                ..TranslatedFrame::default()
+            },
+            (WsProtocol::GraphQLTransportWs, Some("pong")) => TranslatedFrame::default(),
+            (WsProtocol::SubscriptionsTransportWs, Some("start")) => {
+                TranslatedFrame::forward(with_frame_type(frame, "subscribe"))
+            }
+            (WsProtocol::SubscriptionsTransportWs, Some("stop")) => {
+                TranslatedFrame::forward(with_frame_type(frame, "complete"))
+            }
+            (WsProtocol::SubscriptionsTransportWs, Some("connection_terminate")) => {
+                TranslatedFrame::close(close_code::NORMAL, "")
+            }
+            _ => TranslatedFrame::forward(frame),
+        }
+    }
+
+    pub fn translate_server_frame(&self, frame: serde_json::Value) -> TranslatedFrame {
+        let frame_type = frame_type(&frame);
+
+        match (self.server_protocol, frame_type.as_deref()) {
+            (WsProtocol::SubscriptionsTransportWs, Some("data")) => {
+                TranslatedFrame::forward(with_frame_type(frame, "next"))
+            }
+            (WsProtocol::SubscriptionsTransportWs, Some("ka")) => {
+                TranslatedFrame::forward(serde_json::json!({ "type": "pong" }))
+            }
+            (WsProtocol::SubscriptionsTransportWs, Some("error")) => {
+                TranslatedFrame::forward(map_payload(frame, |payload| match payload {
+                    serde_json::Value::Array(errors) => serde_json::Value::Array(errors),
+                    error => serde_json::Value::Array(vec![error]),
+                }))
+            }
+            (WsProtocol::SubscriptionsTransportWs, Some("connection_error")) => {
+                log::warn!("the server rejected the connection, frame = {frame}");
+                TranslatedFrame::close(FORBIDDEN_CLOSE_CODE, "Forbidden")
+            }
+            (WsProtocol::GraphQLTransportWs, Some("next")) => {
+                TranslatedFrame::forward(with_frame_type(frame, "data"))
+            }
+            (WsProtocol::GraphQLTransportWs, Some("error")) => {
+                TranslatedFrame::forward(map_payload(frame, |payload| match payload {
+                    serde_json::Value::Array(errors) => {
+                        errors.into_iter().next().unwrap_or(serde_json::Value::Null)
+                    }
+                    error => error,
+                }))
+            }
+            (WsProtocol::GraphQLTransportWs, Some("ping")) => TranslatedFrame {
+                forwarded_frame: Some(serde_json::json!({ "type": "ka" })),
+                reply_frame: Some(serde_json::json!({ "type": "pong" })),
+                ..TranslatedFrame::default()
+            },
+            (WsProtocol::GraphQLTransportWs, Some("pong")) => TranslatedFrame::default(),
+            _ => TranslatedFrame::forward(frame),
+        }
+    }
+}
+
+fn frame_type(frame: &serde_json::Value) -> Option<String> {
+    frame
+        .get("type")
+        .and_then(|ty| ty.as_str())
+        .map(|ty| ty.to_string())
+}
+
+fn with_frame_type(mut frame: serde_json::Value, frame_type: &str) -> serde_json::Value {
+    if let Some(map) = frame.as_object_mut() {
+        map.insert("type".to_string(), serde_json::Value::from(frame_type));
+    }
+
+    frame
+}
+
+fn map_payload(
+    mut frame: serde_json::Value,
+    f: impl FnOnce(serde_json::Value) -> serde_json::Value,
+) -> serde_json::Value {
+    if let Some(payload) = frame.get_mut("payload") {
+        *payload = f(payload.take());
+    }
+
+    frame
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationFrame {
+    pub protocol: WsProtocol,
+    pub id: serde_json::Value,
+    pub query: String,
+    pub operation_name: Option<String>,
+}
+
+impl OperationFrame {
+    pub fn from_json(frame: &serde_json::Value) -> Option<Self> {
+        let serde_json::Value::Object(map) = frame else {
+            return None;
+        };
+
+        let protocol = WsProtocol::from_operation_frame_type(map.get("type")?.as_str()?)?;
+        let id = map.get("id").cloned().unwrap_or(serde_json::Value::Null);
+        let payload = map.get("payload")?.as_object()?;
+        let query = payload.get("query")?.as_str()?.to_string();
+        let operation_name = payload
+            .get("operationName")
+            .and_then(|operation_name| operation_name.as_str())
+            .map(|operation_name| operation_name.to_string());
+
+        Some(Self {
+            protocol,
+            id,
+            query,
+            operation_name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_frame_from_json() {
+        assert_eq!(
+            OperationFrame::from_json(&serde_json::json!({
+                "id": "1",
+                "type": "subscribe",
+                "payload": { "query": "mutation Foo { foo }", "operationName": "Foo" },
+            })),
+            Some(OperationFrame {
+                protocol: WsProtocol::GraphQLTransportWs,
+                id: serde_json::json!("1"),
+                query: "mutation Foo { foo }".to_string(),
+                operation_name: Some("Foo".to_string()),
+            })
+        );
+
+        assert_eq!(
+            OperationFrame::from_json(&serde_json::json!({
+                "id": 2,
+                "type": "start",
+                "payload": { "query": "{ foo }" },
+            })),
+            Some(OperationFrame {
+                protocol: WsProtocol::SubscriptionsTransportWs,
+                id: serde_json::json!(2),
+                query: "{ foo }".to_string(),
+                operation_name: None,
+            })
+        );
+
+        assert_eq!(
+            OperationFrame::from_json(&serde_json::json!({ "type": "connection_init" })),
+            None
+        );
+        assert_eq!(
+            OperationFrame::from_json(&serde_json::json!({ "id": "1", "type": "subscribe" })),
+            None
+        );
+        assert_eq!(OperationFrame::from_json(&serde_json::json!("foo")), None);
+    }
+
+    #[test]
+    fn test_create_error_frame() {
+        let id = serde_json::json!("1");
+
+        assert_eq!(
+            WsProtocol::GraphQLTransportWs
+                .create_error_frame(&id, vec![ServerError::new("foo", None)]),
+            serde_json::json!({
+                "id": "1",
+                "type": "error",
+                "payload": [{ "message": "foo" }],
+            })
+        );
+
+        assert_eq!(
+            WsProtocol::SubscriptionsTransportWs
+                .create_error_frame(&id, vec![ServerError::new("foo", None)]),
+            serde_json::json!({
+                "id": "1",
+                "type": "error",
+                "payload": { "message": "foo" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_translate_client_frame() {
+        let translation = WsProtocolTranslation::new(
+            WsProtocol::GraphQLTransportWs,
+            WsProtocol::SubscriptionsTransportWs,
+        )
+        .unwrap();
+
+        assert_eq!(
+            translation.translate_client_frame(serde_json::json!({
+                "id": "1",
+                "type": "subscribe",
+                "payload": { "query": "{ foo }" },
+            })),
+            TranslatedFrame::forward(serde_json::json!({
+                "id": "1",
+                "type": "start",
+                "payload": { "query": "{ foo }" },
+            }))
+        );
+        assert_eq!(
+            translation.translate_client_frame(serde_json::json!({ "type": "ping" })),
+            TranslatedFrame {
+                reply_frame: Some(serde_json::json!({ "type": "pong" })),
+                ..TranslatedFrame::default()
+            }
+        );
+        assert_eq!(
+            translation.translate_client_frame(serde_json::json!({ "type": "connection_init" })),
+            TranslatedFrame::forward(serde_json::json!({ "type": "connection_init" }))
+        );
+
+        let translation = WsProtocolTranslation::new(
+            WsProtocol::SubscriptionsTransportWs,
+            WsProtocol::GraphQLTransportWs,
+        )
+        .unwrap();
+
+        assert_eq!(
+            translation.translate_client_frame(serde_json::json!({ "id": "1", "type": "stop" })),
+            TranslatedFrame::forward(serde_json::json!({ "id": "1", "type": "complete" }))
+        );
+        assert_eq!(
+            translation
+                .translate_client_frame(serde_json::json!({ "type": "connection_terminate" })),
+            TranslatedFrame::close(close_code::NORMAL, "")
+        );
+        assert_eq!(
+            WsProtocolTranslation::new(
+                WsProtocol::GraphQLTransportWs,
+                WsProtocol::GraphQLTransportWs
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_translate_server_frame() {
+        let translation = WsProtocolTranslation::new(
+            WsProtocol::GraphQLTransportWs,
+            WsProtocol::SubscriptionsTransportWs,
+        )
+        .unwrap();
+
+        assert_eq!(
+            translation.translate_server_frame(serde_json::json!({
+                "id": "1",
+                "type": "data",
+                "payload": { "data": { "foo": 1 } },
+            })),
+            TranslatedFrame::forward(serde_json::json!({
+                "id": "1",
+                "type": "next",
+                "payload": { "data": { "foo": 1 } },
+            }))
+        );
+        assert_eq!(
+            translation.translate_server_frame(serde_json::json!({
+                "id": "1",
+                "type": "error",
+                "payload": { "message": "foo" },
+            })),
+            TranslatedFrame::forward(serde_json::json!({
+                "id": "1",
+                "type": "error",
+                "payload": [{ "message": "foo" }],
+            }))
+        );
+        assert_eq!(
+            translation.translate_server_frame(serde_json::json!({ "type": "ka" })),
+            TranslatedFrame::forward(serde_json::json!({ "type": "pong" }))
+        );
+        assert_eq!(
+            translation.translate_server_frame(serde_json::json!({
+                "type": "connection_error",
+                "payload": { "message": "foo" },
+            })),
+            TranslatedFrame::close(4403, "Forbidden")
+        );
+
+        let translation = WsProtocolTranslation::new(
+            WsProtocol::SubscriptionsTransportWs,
+            WsProtocol::GraphQLTransportWs,
+        )
+        .unwrap();
+
+        assert_eq!(
+            translation.translate_server_frame(serde_json::json!({
+                "id": "1",
+                "type": "error",
+                "payload": [{ "message": "foo" }, { "message": "bar" }],
+            })),
+            TranslatedFrame::forward(serde_json::json!({
+                "id": "1",
+                "type": "error",
+                "payload": { "message": "foo" },
+            }))
+        );
+        assert_eq!(
+            translation.translate_server_frame(serde_json::json!({ "type": "ping" })),
+            TranslatedFrame {
+                forwarded_frame: Some(serde_json::json!({ "type": "ka" })),
+                reply_frame: Some(serde_json::json!({ "type": "pong" })),
+                close_frame: None,
+            }
+        );
+    }
+}
@@ -1,23 +1,25 @@
-use std::sync::{
-    atomic::{self, AtomicU64},
-    Arc,
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc,
+    },
+    time::{Duration, SystemTime},
 };
 
-use async_graphql::{Response, ServerError};
-use async_graphql_axum::GraphQLResponse;
+use async_graphql_parser::{parse_query, types::OperationType};
 use axum::{
     extract::{
         ws::{CloseFrame as AxumCloseFrame, Message as AxumWsMessage, WebSocket},
-        WebSocketUpgrade,
+        ConnectInfo, WebSocketUpgrade,
     },
-    http::HeaderMap,
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
 };
 use futures_util::{SinkExt, StreamExt};
-use tokio::{
-    net::TcpStream,
-    sync::{broadcast, mpsc},
-};
+use parking_lot::Mutex;
+use tokio::{net::TcpStream, sync::mpsc};
 use tokio_tungstenite::{
     tungstenite::{
         client::IntoClientRequest,
@@ -30,28 +32,38 @@ use tokio_tungstenite::{
 };
 
 use crate::{
-    admin_state::ConnectionId,
+    admin_state::{AdminState, ConnectionId},
     app_state::AppState,
+    breakpoints::{BreakpointResolution, BreakpointTarget, Breakpoints},
+    error::ProxyError,
     log_location,
+    message_sender::MessageSender,
     model::{
-        enums::{connection_type::ConnectionType, message_direction::MessageDirection},
-        types::{headers::Headers, message::Message},
+        enums::{
+            connection_type::ConnectionType, message_direction::MessageDirection,
+            message_origin::MessageOrigin,
+        },
+        types::{connection_init_rule::ConnectionInitRule, headers::Headers, message::Message},
     },
     utils::move_and_replace_headers,
+    ws_connections::{WsChannels, WsConnection},
+};
+
+use super::{
+    fault_injection::{FaultAction, WsFaultInjector},
+    graphql_proxy::is_query_of_type,
+    graphql_ws_operation_tracker::{WsOperation, WsOperationTracker},
+    graphql_ws_protocol::{OperationFrame, TranslatedFrame, WsProtocol, WsProtocolTranslation},
 };
 
 pub async fn get_graphql_ws_proxy(
     mut headers: HeaderMap,
     axum::extract::State(state): axum::extract::State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     ws: WebSocketUpgrade,
-) -> Result<impl IntoResponse, GraphQLResponse> {
-    let server_endpoint_url = Arc::new(
-        state
-            .admin_state()
-            .server_graphql_endpoints_read()
-            .graphql_ws_endpoint
-            .clone(),
-    );
+) -> Result<impl IntoResponse, ProxyError> {
+    let mut upstream_settings = state.admin_state().upstream_settings();
+    let server_endpoint_url = Arc::new(upstream_settings.server_endpoints.graphql_ws_endpoint);
 
     log::debug!(
         "Starting ws connection with endpoint: '{}'",
@@ -60,16 +72,21 @@ pub async fn get_graphql_ws_proxy(
 
     log::debug!("GaphQL WS request headers = {:?}", headers);
 
+    if state.cassette().is_some() {
+        return Ok(get_replayed_graphql_ws(
+            headers,
+            state,
+            ws,
+            server_endpoint_url,
+        ));
+    }
+
+    let client_protocol = find_client_protocol(&headers);
+
     let mut request = server_endpoint_url
         .as_ref()
         .into_client_request()
-        .inspect_err(|e| log::error!("{}, {}", log_location!(), e.to_string()))
-        .map_err(|e| {
-            GraphQLResponse::from(Response::from_errors(vec![ServerError::new(
-                e.to_string(),
-                None,
-            )]))
-        })?;
+        .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))?;
 
     const PROHIBITED_HEADER_NAMES_TO_SERVER: &[&str] = &[
         "host",
@@ -86,51 +103,92 @@ pub async fn get_graphql_ws_proxy(
         PROHIBITED_HEADER_NAMES_TO_SERVER,
     );
 
-    let mut additional_request_headers = state.admin_state().request_headers().read().clone();
-    move_and_replace_headers(request.headers_mut(), &mut additional_request_headers, &[]);
+    // both subprotocols are offered to the server, the frames are translated when it picks the
+    // one the client did not ask for
+    if let Some(client_protocol) = client_protocol {
+        if let Ok(offered_subprotocols) = HeaderValue::from_str(&format!(
+            "{}, {}",
+            client_protocol.subprotocol(),
+            client_protocol.other().subprotocol()
+        )) {
+            request
+                .headers_mut()
+                .insert(http::header::SEC_WEBSOCKET_PROTOCOL, offered_subprotocols);
+        }
+    }
 
-    let message_sender = state.admin_state().message_sender_ref().clone();
+    move_and_replace_headers(
+        request.headers_mut(),
+        &mut upstream_settings.request_headers,
+        &[],
+    );
 
-    let connection_id = ConnectionId::new();
-    let sequence_counter = Arc::new(AtomicU64::new(0));
-    send_message_to_subscriptions(
-        connection_id.clone(),
-        &sequence_counter,
+    let message_capture = WsMessageCapture::new(
+        ConnectionId::new(),
+        state.admin_state().message_sender_ref().clone(),
+        server_endpoint_url,
+    );
+    message_capture.send_message_to_subscriptions(
         serde_json::Value::Null,
         MessageDirection::Request,
-        &message_sender,
         Some(Arc::new(Headers::from_header_map(
             request.headers().clone(),
         ))),
-        server_endpoint_url.clone(),
+        MessageOrigin::Client,
     );
 
-    let (ws_stream, mut server_response) = tokio_tungstenite::connect_async(request)
-        .await
-        .inspect_err(|e| log::error!("{}, {}", log_location!(), e.to_string()))
-        .map_err(|e| {
-            GraphQLResponse::from(Response::from_errors(vec![ServerError::new(
-                e.to_string(),
-                None,
-            )]))
-        })?;
+    let fault_injector = Arc::new(WsFaultInjector::new(state.admin_state()));
+    let handshake_fault_action = fault_injector.handshake_action();
+    tokio::time::sleep(handshake_fault_action.latency).await;
+    if handshake_fault_action.status.is_some() {
+        return Ok(reject_with_fault(&message_capture, &handshake_fault_action));
+    }
+
+    let (ws_stream, mut server_response) = tokio_tungstenite::connect_async_tls_with_config(
+        request,
+        None,
+        false,
+        Some(state.ws_connector(upstream_settings.accept_invalid_certificates)),
+    )
+    .await
+    .map_err(|e| ProxyError::from_tungstenite_error(log_location!(), e))?;
 
     log::debug!("Websocket server response = {:?}", server_response);
 
+    let protocol_translation = client_protocol.and_then(|client_protocol| {
+        let server_protocol = server_response
+            .headers_mut()
+            .remove(http::header::SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|subprotocol| {
+                WsProtocol::from_subprotocol(subprotocol.to_str().ok()?.trim())
+            })?;
+        log::debug!(
+            "negotiated ws subprotocols, client = {:?}, server = {:?}",
+            client_protocol,
+            server_protocol
+        );
+
+        WsProtocolTranslation::new(client_protocol, server_protocol)
+    });
+
+    let ws = match client_protocol {
+        Some(client_protocol) => ws.protocols([client_protocol.subprotocol()]),
+        None => ws,
+    };
+
     let mut response = {
-        let message_sender = message_sender.clone();
-        let connection_id = connection_id.clone();
-        let sequence_counter = sequence_counter.clone();
-        let server_endpoint_url = server_endpoint_url.clone();
+        let admin_state = state.admin_state().clone();
+        let message_capture = message_capture.clone();
 
         ws.on_upgrade(move |socket| {
             handle_socket(
                 socket,
                 ws_stream,
-                message_sender,
-                connection_id,
-                sequence_counter,
-                server_endpoint_url,
+                admin_state,
+                message_capture,
+                fault_injector,
+                protocol_translation,
+                connect_info.map(|ConnectInfo(client_address)| client_address.to_string()),
             )
         })
     };
@@ -159,90 +217,308 @@ pub async fn get_graphql_ws_proxy(
         &[],
     );
 
-    send_message_to_subscriptions(
-        connection_id.clone(),
-        &sequence_counter,
+    message_capture.send_message_to_subscriptions(
         serde_json::Value::Null,
         MessageDirection::Response,
-        &message_sender,
         Some(Arc::new(Headers::from_header_map(
             response.headers().clone(),
         ))),
-        server_endpoint_url,
+        MessageOrigin::Server,
     );
 
     Ok(response)
 }
 
+fn find_client_protocol(headers: &HeaderMap) -> Option<WsProtocol> {
+    headers
+        .get_all(http::header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|subprotocol| WsProtocol::from_subprotocol(subprotocol.trim()))
+}
+
+fn reject_with_fault(
+    message_capture: &WsMessageCapture,
+    fault_action: &FaultAction,
+) -> axum::response::Response {
+    log::debug!(
+        "rejecting the ws handshake with an injected fault = {:?}",
+        fault_action
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        http::header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+
+    let response = serde_json::json!({ "errors": [fault_action.create_error()] });
+    let text = response.to_string();
+
+    message_capture.send_message_to_subscriptions(
+        response,
+        MessageDirection::Response,
+        Some(Arc::new(Headers::from_header_map(headers.clone()))),
+        MessageOrigin::Fault,
+    );
+
+    (
+        fault_action
+            .status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::SERVICE_UNAVAILABLE),
+        headers,
+        text,
+    )
+        .into_response()
+}
+
+fn get_replayed_graphql_ws(
+    mut headers: HeaderMap,
+    state: AppState,
+    ws: WebSocketUpgrade,
+    server_endpoint_url: Arc<String>,
+) -> axum::response::Response {
+    const PROHIBITED_HEADER_NAMES_TO_SERVER: &[&str] = &[
+        "host",
+        "content-length",
+        "connection",
+        "upgrade",
+        "sec-websocket-key",
+        "sec-websocket-version",
+    ];
+
+    let mut request_headers = HeaderMap::new();
+    move_and_replace_headers(
+        &mut request_headers,
+        &mut headers,
+        PROHIBITED_HEADER_NAMES_TO_SERVER,
+    );
+
+    let mut additional_request_headers = state.admin_state().request_headers().read().clone();
+    move_and_replace_headers(&mut request_headers, &mut additional_request_headers, &[]);
+
+    let message_capture = WsMessageCapture::new(
+        ConnectionId::new(),
+        state.admin_state().message_sender_ref().clone(),
+        server_endpoint_url,
+    );
+    message_capture.send_message_to_subscriptions(
+        serde_json::Value::Null,
+        MessageDirection::Request,
+        Some(Arc::new(Headers::from_header_map(request_headers))),
+        MessageOrigin::Client,
+    );
+
+    let mut response = {
+        let state = state.clone();
+        let message_capture = message_capture.clone();
+
+        ws.protocols(["graphql-transport-ws", "graphql-ws"])
+            .on_upgrade(move |socket| handle_replayed_socket(socket, state, message_capture))
+    };
+
+    let mut additional_response_headers = state.admin_state().response_headers().read().clone();
+    move_and_replace_headers(
+        response.headers_mut(),
+        &mut additional_response_headers,
+        &[],
+    );
+
+    message_capture.send_message_to_subscriptions(
+        serde_json::Value::Null,
+        MessageDirection::Response,
+        Some(Arc::new(Headers::from_header_map(
+            response.headers().clone(),
+        ))),
+        MessageOrigin::Proxy,
+    );
+
+    response
+}
+
+async fn handle_replayed_socket(
+    mut client_stream: WebSocket,
+    state: AppState,
+    message_capture: WsMessageCapture,
+) {
+    let Some(cassette) = state.cassette() else {
+        return;
+    };
+
+    while let Some(message) = client_stream.next().await {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                log::error!(
+                    "{}, error reading from client, error = '{e}'",
+                    log_location!()
+                );
+                continue;
+            }
+        };
+
+        message_capture.send_axum_ws_message_to_subscriptions(
+            &message,
+            MessageDirection::Request,
+            None,
+            MessageOrigin::Client,
+        );
+
+        let AxumWsMessage::Text(text) = message else {
+            continue;
+        };
+        let Ok(frame) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+
+        let (response_frames, origin) = match frame.get("type").and_then(|ty| ty.as_str()) {
+            Some("connection_init") => (
+                vec![serde_json::json!({ "type": "connection_ack" })],
+                MessageOrigin::Proxy,
+            ),
+            Some("ping") => (
+                vec![serde_json::json!({ "type": "pong" })],
+                MessageOrigin::Proxy,
+            ),
+            Some("connection_terminate") => break,
+            _ => match OperationFrame::from_json(&frame) {
+                Some(operation_frame) => frame
+                    .get("payload")
+                    .and_then(|payload| {
+                        cassette.find_ws_response_frames(payload, &operation_frame.id)
+                    })
+                    .map(|response_frames| (response_frames, MessageOrigin::Cassette))
+                    .unwrap_or_else(|| {
+                        let error = ProxyError::MissingRecordedResponse {
+                            location: log_location!(),
+                        };
+                        log::error!("{}, operation = {frame}", error.message());
+                        let error_frame = operation_frame
+                            .protocol
+                            .create_error_frame(&operation_frame.id, vec![error.to_server_error()]);
+                        (vec![error_frame], MessageOrigin::Proxy)
+                    }),
+                None => continue,
+            },
+        };
+
+        for response_frame in response_frames {
+            let response_message = AxumWsMessage::Text(response_frame.to_string());
+            message_capture.send_axum_ws_message_to_subscriptions(
+                &response_message,
+                MessageDirection::Response,
+                None,
+                origin,
+            );
+
+            if client_stream.send(response_message).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    log::debug!("connection to the client is closed");
+}
+
+#[derive(Clone)]
+struct WsSession {
+    admin_state: AdminState,
+    message_capture: WsMessageCapture,
+    fault_injector: Arc<WsFaultInjector>,
+    operation_tracker: Arc<WsOperationTracker>,
+    connection: Arc<WsConnection>,
+    protocol_translation: Option<WsProtocolTranslation>,
+}
+
+// the frames are processed by a forwarder per direction, so a delayed or paused frame holds back
+// only the frames of its own direction, and the pumps keep handling the close of the connection
 async fn handle_socket(
     client_stream: WebSocket,
     server_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    message_sender: broadcast::Sender<Message>,
-    connection_id: ConnectionId,
-    sequence_counter: Arc<AtomicU64>,
-    server_endpoint_url: Arc<String>,
+    admin_state: AdminState,
+    message_capture: WsMessageCapture,
+    fault_injector: Arc<WsFaultInjector>,
+    protocol_translation: Option<WsProtocolTranslation>,
+    client_address: Option<String>,
 ) {
     let (server_to_client_sender, server_to_client_receiver) = mpsc::unbounded_channel();
     let (client_to_server_sender, client_to_server_receiver) = mpsc::unbounded_channel();
+    let (server_frame_sender, server_frame_receiver) = mpsc::unbounded_channel();
+    let (client_frame_sender, client_frame_receiver) = mpsc::unbounded_channel();
 
-    {
-        let message_sender = message_sender.clone();
-        let connection_id = connection_id.clone();
-        let sequence_counter = sequence_counter.clone();
-        let server_endpoint_url = server_endpoint_url.clone();
-
-        tokio::spawn(async move {
-            handle_server_stream(
-                connection_id,
-                sequence_counter,
-                server_stream,
-                server_to_client_sender,
-                client_to_server_receiver,
-                message_sender,
-                server_endpoint_url,
-            )
-            .await;
-        });
-    }
+    let connection_guard = admin_state.ws_connections().register(
+        message_capture.connection_id.as_arc_string(),
+        client_address,
+        message_capture.server_endpoint_url.clone(),
+        message_capture.clone(),
+        WsChannels::new(&server_to_client_sender, &client_to_server_sender),
+    );
+    let connection = connection_guard.connection().clone();
 
+    let session = WsSession {
+        admin_state,
+        message_capture,
+        fault_injector,
+        operation_tracker: Arc::new(WsOperationTracker::new()),
+        connection: connection.clone(),
+        protocol_translation,
+    };
+
+    let forwarders = [
+        tokio::spawn(forward_server_frames(
+            session.clone(),
+            server_frame_receiver,
+            server_to_client_sender.clone(),
+            client_to_server_sender.clone(),
+        )),
+        tokio::spawn(forward_client_frames(
+            session,
+            client_frame_receiver,
+            client_to_server_sender.clone(),
+            server_to_client_sender,
+        )),
+    ];
+
+    let server_pump = tokio::spawn(handle_server_stream(
+        connection.clone(),
+        server_stream,
+        server_frame_sender,
+        client_to_server_receiver,
+    ));
     handle_client_stream(
-        connection_id,
-        sequence_counter,
+        connection,
         client_stream,
+        client_frame_sender,
         client_to_server_sender,
         server_to_client_receiver,
-        message_sender,
-        server_endpoint_url,
     )
     .await;
+    let _ = server_pump.await;
+
+    // a frame that is still paused is not delivered to a closed connection
+    for forwarder in forwarders {
+        forwarder.abort();
+    }
 }
 
 async fn handle_server_stream(
-    connection_id: ConnectionId,
-    sequence_counter: Arc<AtomicU64>,
+    connection: Arc<WsConnection>,
     mut server_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    server_to_client_sender: mpsc::UnboundedSender<AxumWsMessage>,
+    server_frame_sender: mpsc::UnboundedSender<AxumWsMessage>,
     mut client_to_server_receiver: mpsc::UnboundedReceiver<AxumWsMessage>,
-    message_sender: broadcast::Sender<Message>,
-    server_endpoint_url: Arc<String>,
 ) {
+    let mut close_receiver = connection.subscribe_close();
+
     loop {
         tokio::select! {
+            biased;
+
             message = server_stream.next() => {
                 match message {
                     Some(Ok(message)) => {
-                        let message = tungstenite_to_axum_message(message);
-                        send_axum_ws_message_to_subscriptions(
-                            connection_id.clone(),
-                            &sequence_counter,
-                            &message,
-                            MessageDirection::Response,
-                            &message_sender,
-                            None,
-                            server_endpoint_url.clone(),
-                        );
-
-                        if server_to_client_sender.send(message).is_err() {
+                        if server_frame_sender.send(tungstenite_to_axum_message(message)).is_err() {
                             break;
                         }
                     }
@@ -269,34 +545,36 @@ async fn handle_server_stream(
                     None => break,
                 }
             }
+            Ok(()) = close_receiver.changed() => {
+                let close_frame = close_receiver.borrow_and_update().clone();
+                log::debug!("closing the connection to the server, close frame = {:?}", close_frame);
+
+                let _ = server_stream
+                    .send(TungsteniteMessage::Close(close_frame.map(axum_to_tungstenite_close_frame)))
+                    .await;
+                break;
+            }
         }
     }
 }
 
 async fn handle_client_stream(
-    connection_id: ConnectionId,
-    sequence_counter: Arc<AtomicU64>,
+    connection: Arc<WsConnection>,
     mut client_stream: WebSocket,
+    client_frame_sender: mpsc::UnboundedSender<AxumWsMessage>,
     client_to_server_sender: mpsc::UnboundedSender<AxumWsMessage>,
     mut server_to_client_receiver: mpsc::UnboundedReceiver<AxumWsMessage>,
-    message_sender: broadcast::Sender<Message>,
-    server_endpoint_url: Arc<String>,
 ) {
+    let mut close_receiver = connection.subscribe_close();
+
     loop {
         tokio::select! {
+            biased;
+
             message = client_stream.next() => {
                 match message {
                     Some(Ok(message)) => {
-                        send_axum_ws_message_to_subscriptions(
-                            connection_id.clone(),
-                            &sequence_counter,
-                            &message,
-                            MessageDirection::Request,
-                            &message_sender,
-                            None,
-                            server_endpoint_url.clone(),
-                        );
-                        if client_to_server_sender.send(message).is_err() {
+                        if client_frame_sender.send(message).is_err() {
                             break;
                         }
                     }
@@ -326,77 +604,527 @@ async fn handle_client_stream(
                     None => break,
                 }
             }
+            Ok(()) = close_receiver.changed() => {
+                let close_frame = close_receiver.borrow_and_update().clone();
+                log::debug!("closing the connection to the client, close frame = {:?}", close_frame);
+
+                let _ = client_stream.send(AxumWsMessage::Close(close_frame)).await;
+                break;
+            }
         }
     }
 }
 
-fn send_message_to_subscriptions(
-    connection_id: ConnectionId,
-    sequence_counter: &AtomicU64,
-    message: serde_json::Value,
-    message_direction: MessageDirection,
-    message_sender: &broadcast::Sender<Message>,
-    transmitted_headers: Option<Arc<Headers>>,
-    server_endpoint_url: Arc<String>,
+async fn forward_server_frames(
+    session: WsSession,
+    mut server_frame_receiver: mpsc::UnboundedReceiver<AxumWsMessage>,
+    server_to_client_sender: mpsc::UnboundedSender<AxumWsMessage>,
+    client_to_server_sender: mpsc::UnboundedSender<AxumWsMessage>,
 ) {
-    let sequence_counter = sequence_counter.fetch_add(1, atomic::Ordering::SeqCst);
-    let _ = message_sender.send(Message {
-        connection_id: connection_id.as_arc_string(),
-        message: Arc::new(message),
-        sequence_counter,
+    let WsSession {
+        admin_state,
+        message_capture,
+        fault_injector,
+        operation_tracker,
+        connection,
+        protocol_translation,
+    } = session;
+
+    while let Some(message) = server_frame_receiver.recv().await {
+        let operation = operation_tracker.find_server_message_operation(&message);
+        let fault_action = fault_injector.server_message_action(operation.as_ref());
+        tokio::time::sleep(fault_action.latency).await;
+        let (message, is_faulty) =
+            fault_injector.apply_to_server_message(message, &fault_action, operation.as_ref());
+
+        message_capture.send_axum_ws_message_to_subscriptions(
+            &message,
+            MessageDirection::Response,
+            None,
+            if is_faulty {
+                MessageOrigin::Fault
+            } else {
+                MessageOrigin::Server
+            },
+        );
+
+        let Some(message) = pause_at_breakpoint(
+            admin_state.breakpoints(),
+            &message_capture,
+            operation.clone(),
+            MessageDirection::Response,
+            message,
+        )
+        .await
+        else {
+            continue;
+        };
+
+        let (message, reply, close_frame) = match protocol_translation {
+            Some(protocol_translation) => translate_message(
+                &message_capture,
+                MessageDirection::Response,
+                message,
+                |frame| protocol_translation.translate_server_frame(frame),
+            ),
+            None => (Some(message), None, None),
+        };
+
+        if let Some(close_frame) = close_frame {
+            log::debug!("closing the ws connection, close frame = {close_frame:?}");
+            connection.close(close_frame);
+            continue;
+        }
+
+        if let Some(reply) = reply {
+            if client_to_server_sender.send(reply).is_err() {
+                break;
+            }
+        }
+
+        if let Some(message) = message {
+            connection.track_server_message(&message);
+
+            if server_to_client_sender.send(message).is_err() {
+                break;
+            }
+        }
+
+        if fault_injector.is_closing_after_server_message(operation.as_ref()) {
+            log::debug!("closing the ws connection with an injected fault");
+            connection.close(fault_injector.create_close_frame());
+            break;
+        }
+    }
+
+    connection.stop();
+}
+
+async fn forward_client_frames(
+    session: WsSession,
+    mut client_frame_receiver: mpsc::UnboundedReceiver<AxumWsMessage>,
+    client_to_server_sender: mpsc::UnboundedSender<AxumWsMessage>,
+    server_to_client_sender: mpsc::UnboundedSender<AxumWsMessage>,
+) {
+    let WsSession {
+        admin_state,
+        message_capture,
+        operation_tracker,
+        connection,
+        protocol_translation,
+        ..
+    } = session;
+    let mut is_connection_init_received = false;
+
+    while let Some(message) = client_frame_receiver.recv().await {
+        message_capture.send_axum_ws_message_to_subscriptions(
+            &message,
+            MessageDirection::Request,
+            None,
+            MessageOrigin::Client,
+        );
+
+        let message = match (!is_connection_init_received)
+            .then(|| rewrite_connection_init(&admin_state, &message))
+            .flatten()
+        {
+            Some(rewritten_message) => {
+                is_connection_init_received = true;
+                if rewritten_message != message {
+                    message_capture.send_axum_ws_message_to_subscriptions(
+                        &rewritten_message,
+                        MessageDirection::Request,
+                        None,
+                        MessageOrigin::Proxy,
+                    );
+                }
+
+                rewritten_message
+            }
+            None => message,
+        };
+
+        let operation =
+            WsOperation::from_client_message(&message).map(|(_id, operation)| operation);
+        let Some(message) = pause_at_breakpoint(
+            admin_state.breakpoints(),
+            &message_capture,
+            operation,
+            MessageDirection::Request,
+            message,
+        )
+        .await
+        else {
+            continue;
+        };
+        operation_tracker.track_client_message(&message);
+
+        if let Some(rejection) = reject_prohibited_operation(&admin_state, &message) {
+            message_capture.send_axum_ws_message_to_subscriptions(
+                &rejection,
+                MessageDirection::Response,
+                None,
+                MessageOrigin::Proxy,
+            );
+
+            if server_to_client_sender.send(rejection).is_err() {
+                break;
+            }
+
+            continue;
+        }
+
+        connection.track_client_message(&message);
+
+        let (message, reply, close_frame) = match protocol_translation {
+            Some(protocol_translation) => translate_message(
+                &message_capture,
+                MessageDirection::Request,
+                message,
+                |frame| protocol_translation.translate_client_frame(frame),
+            ),
+            None => (Some(message), None, None),
+        };
+
+        if let Some(close_frame) = close_frame {
+            log::debug!("closing the ws connection, close frame = {close_frame:?}");
+            connection.close(close_frame);
+            continue;
+        }
+
+        if let Some(reply) = reply {
+            if server_to_client_sender.send(reply).is_err() {
+                break;
+            }
+        }
+
+        let Some(message) = message else {
+            continue;
+        };
+        if client_to_server_sender.send(message).is_err() {
+            break;
+        }
+    }
+
+    connection.stop();
+}
+
+async fn pause_at_breakpoint(
+    breakpoints: &Breakpoints,
+    message_capture: &WsMessageCapture,
+    operation: Option<WsOperation>,
+    message_direction: MessageDirection,
+    message: AxumWsMessage,
+) -> Option<AxumWsMessage> {
+    let AxumWsMessage::Text(text) = &message else {
+        return Some(message);
+    };
+
+    let (operation_name, operation_type) = operation
+        .map(|operation| (operation.operation_name, operation.operation_type))
+        .unwrap_or_default();
+    let target = BreakpointTarget {
+        connection_id: message_capture.connection_id.as_arc_string(),
         connection_type: ConnectionType::Ws,
-        message_direction,
-        transmitted_headers,
-        server_endpoint_url,
+        operation_name,
+        operation_type,
+    };
+    if !breakpoints.is_matching(&target, message_direction) {
+        return Some(message);
+    }
+
+    let json = serde_json::from_str::<serde_json::Value>(text)
+        .unwrap_or_else(|_| serde_json::Value::from(text.clone()));
+
+    match breakpoints
+        .pause_if_matching(&target, message_direction, json)
+        .await
+    {
+        BreakpointResolution::Resume => Some(message),
+        BreakpointResolution::Edit(edited_message) => {
+            let edited_message = AxumWsMessage::Text(match edited_message {
+                serde_json::Value::String(text) => text,
+                edited_message => edited_message.to_string(),
+            });
+            message_capture.send_axum_ws_message_to_subscriptions(
+                &edited_message,
+                message_direction,
+                None,
+                MessageOrigin::Breakpoint,
+            );
+
+            Some(edited_message)
+        }
+        BreakpointResolution::Drop => {
+            log::debug!("dropping the ws message at a breakpoint");
+            None
+        }
+    }
+}
+
+fn translate_message(
+    message_capture: &WsMessageCapture,
+    message_direction: MessageDirection,
+    message: AxumWsMessage,
+    translate: impl FnOnce(serde_json::Value) -> TranslatedFrame,
+) -> (
+    Option<AxumWsMessage>,
+    Option<AxumWsMessage>,
+    Option<AxumCloseFrame<'static>>,
+) {
+    let AxumWsMessage::Text(text) = &message else {
+        return (Some(message), None, None);
+    };
+    let Ok(frame) = serde_json::from_str::<serde_json::Value>(text) else {
+        return (Some(message), None, None);
+    };
+
+    let TranslatedFrame {
+        forwarded_frame,
+        reply_frame,
+        close_frame,
+    } = translate(frame.clone());
+
+    let forwarded_message = match forwarded_frame {
+        Some(forwarded_frame) if forwarded_frame == frame => Some(message),
+        Some(forwarded_frame) => {
+            let forwarded_message = AxumWsMessage::Text(forwarded_frame.to_string());
+            message_capture.send_axum_ws_message_to_subscriptions(
+                &forwarded_message,
+                message_direction,
+                None,
+                MessageOrigin::Proxy,
+            );
+
+            Some(forwarded_message)
+        }
+        None => {
+            log::debug!(
+                "the ws frame has no counterpart in the other subprotocol, frame = {frame}"
+            );
+            None
+        }
+    };
+
+    let reply_message = reply_frame.map(|reply_frame| {
+        let reply_message = AxumWsMessage::Text(reply_frame.to_string());
+        message_capture.send_axum_ws_message_to_subscriptions(
+            &reply_message,
+            match message_direction {
+                MessageDirection::Request => MessageDirection::Response,
+                MessageDirection::Response => MessageDirection::Request,
+            },
+            None,
+            MessageOrigin::Proxy,
+        );
+
+        reply_message
     });
+
+    (forwarded_message, reply_message, close_frame)
 }
 
-fn send_axum_ws_message_to_subscriptions(
-    connection_id: ConnectionId,
-    sequence_counter: &AtomicU64,
+fn rewrite_connection_init(
+    admin_state: &AdminState,
     message: &AxumWsMessage,
-    message_direction: MessageDirection,
-    message_sender: &broadcast::Sender<Message>,
-    transmitted_headers: Option<Arc<Headers>>,
+) -> Option<AxumWsMessage> {
+    let AxumWsMessage::Text(text) = message else {
+        return None;
+    };
+
+    let mut frame = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    if frame.get("type").and_then(|ty| ty.as_str()) != Some("connection_init") {
+        return None;
+    }
+
+    let connection_init_rules = admin_state.connection_init_rules().read();
+    if connection_init_rules.is_empty() {
+        return Some(message.clone());
+    }
+
+    let payload = frame
+        .as_object_mut()?
+        .entry("payload")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if payload.is_null() {
+        *payload = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let serde_json::Value::Object(payload) = payload else {
+        log::warn!(
+            "the payload of the connection_init frame is not an object, it is not rewritten"
+        );
+        return Some(message.clone());
+    };
+
+    ConnectionInitRule::apply_all(&connection_init_rules, payload);
+
+    Some(AxumWsMessage::Text(frame.to_string()))
+}
+
+fn reject_prohibited_operation(
+    admin_state: &AdminState,
+    message: &AxumWsMessage,
+) -> Option<AxumWsMessage> {
+    if !admin_state.prohibit_mutation() {
+        return None;
+    }
+
+    let AxumWsMessage::Text(text) = message else {
+        return None;
+    };
+
+    let operation_frame =
+        OperationFrame::from_json(&serde_json::from_str::<serde_json::Value>(text).ok()?)?;
+
+    // documents that cannot be parsed are forwarded, so the server reports the syntax error
+    let parsed_graphql_query = parse_query(&operation_frame.query).ok()?;
+
+    if is_query_of_type(
+        operation_frame.operation_name.as_ref(),
+        parsed_graphql_query,
+        OperationType::Mutation,
+    ) {
+        let error =
+            ProxyError::policy_rejection(log_location!(), "Mutations are set to be prohibited");
+        log::error!("{}", error.message());
+        let error_frame = operation_frame
+            .protocol
+            .create_error_frame(&operation_frame.id, vec![error.to_server_error()]);
+
+        Some(AxumWsMessage::Text(error_frame.to_string()))
+    } else {
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct WsMessageCapture {
+    connection_id: ConnectionId,
+    sequence_counter: Arc<AtomicU64>,
+    request_timestamps: Arc<Mutex<HashMap<Option<String>, SystemTime>>>,
+    message_sender: MessageSender,
     server_endpoint_url: Arc<String>,
-) {
-    if message_sender.receiver_count() != 0 {
-        match message {
-            AxumWsMessage::Text(text) => {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(text) {
-                    send_message_to_subscriptions(
-                        connection_id,
-                        sequence_counter,
-                        json,
-                        message_direction,
-                        message_sender,
-                        transmitted_headers,
-                        server_endpoint_url,
-                    );
-                } else {
-                    send_message_to_subscriptions(
-                        connection_id,
-                        sequence_counter,
-                        serde_json::Value::from(text.clone()),
+}
+
+impl WsMessageCapture {
+    pub fn new(
+        connection_id: ConnectionId,
+        message_sender: MessageSender,
+        server_endpoint_url: Arc<String>,
+    ) -> Self {
+        Self {
+            connection_id,
+            sequence_counter: Arc::new(AtomicU64::new(0)),
+            request_timestamps: Arc::new(Mutex::new(HashMap::new())),
+            message_sender,
+            server_endpoint_url,
+        }
+    }
+
+    pub fn send_message_to_subscriptions(
+        &self,
+        message: serde_json::Value,
+        message_direction: MessageDirection,
+        transmitted_headers: Option<Arc<Headers>>,
+        origin: MessageOrigin,
+    ) {
+        let timestamp = SystemTime::now();
+        let latency = self.track_latency(&message, message_direction, timestamp);
+
+        let sequence_counter = self.sequence_counter.fetch_add(1, atomic::Ordering::SeqCst);
+        self.message_sender.send(Message {
+            event_id: 0,
+            connection_id: self.connection_id.as_arc_string(),
+            message: Arc::new(message),
+            sequence_counter,
+            connection_type: ConnectionType::Ws,
+            message_direction,
+            transmitted_headers,
+            server_endpoint_url: self.server_endpoint_url.clone(),
+            timestamp,
+            latency,
+            origin,
+            replayed_event_id: None,
+            status_code: None,
+            http_method: None,
+        });
+    }
+
+    pub fn send_axum_ws_message_to_subscriptions(
+        &self,
+        message: &AxumWsMessage,
+        message_direction: MessageDirection,
+        transmitted_headers: Option<Arc<Headers>>,
+        origin: MessageOrigin,
+    ) {
+        if self.message_sender.has_listeners() {
+            match message {
+                AxumWsMessage::Text(text) => {
+                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(text) {
+                        self.send_message_to_subscriptions(
+                            json,
+                            message_direction,
+                            transmitted_headers,
+                            origin,
+                        );
+                    } else {
+                        self.send_message_to_subscriptions(
+                            serde_json::Value::from(text.clone()),
+                            message_direction,
+                            transmitted_headers,
+                            origin,
+                        );
+                    }
+                }
+                AxumWsMessage::Binary(value) => {
+                    self.send_message_to_subscriptions(
+                        serde_json::Value::from(value.clone()),
                         message_direction,
-                        message_sender,
                         transmitted_headers,
-                        server_endpoint_url,
+                        origin,
                     );
                 }
+                _ => (),
             }
-            AxumWsMessage::Binary(value) => {
-                send_message_to_subscriptions(
-                    connection_id,
-                    sequence_counter,
-                    serde_json::Value::from(value.clone()),
-                    message_direction,
-                    message_sender,
-                    transmitted_headers,
-                    server_endpoint_url,
-                );
+        }
+    }
+
+    fn track_latency(
+        &self,
+        message: &serde_json::Value,
+        message_direction: MessageDirection,
+        timestamp: SystemTime,
+    ) -> Option<Duration> {
+        let operation_id = message.get("id").map(|id| match id {
+            serde_json::Value::String(id) => id.clone(),
+            id => id.to_string(),
+        });
+        let is_operation_finished = operation_id.is_some()
+            && matches!(
+                message.get("type").and_then(|ty| ty.as_str()),
+                Some("complete" | "error" | "stop")
+            );
+
+        let mut request_timestamps = self.request_timestamps.lock();
+        match message_direction {
+            MessageDirection::Request if is_operation_finished => {
+                request_timestamps.remove(&operation_id);
+                None
+            }
+            MessageDirection::Request => {
+                request_timestamps.insert(operation_id, timestamp);
+                None
+            }
+            MessageDirection::Response => {
+                let request_timestamp = if is_operation_finished {
+                    request_timestamps.remove(&operation_id)
+                } else {
+                    request_timestamps.get(&operation_id).copied()
+                };
+
+                request_timestamp
+                    .and_then(|request_timestamp| timestamp.duration_since(request_timestamp).ok())
             }
-            _ => (),
         }
     }
 }
@@ -445,3 +1173,82 @@ fn axum_to_tungstenite_close_frame(close_frame: AxumCloseFrame) -> TungsteniteCl
         reason: close_frame.reason,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::types::fault_rule::FaultRule;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_server_message_latency_does_not_delay_client_messages() {
+        let admin_state = AdminState::new("", "", false, HeaderMap::new(), HeaderMap::new(), 0);
+        admin_state.fault_rules().write().push(FaultRule {
+            id: "latency".to_string(),
+            connection_type: Some(ConnectionType::Ws),
+            operation_name: None,
+            operation_type: None,
+            percentage: 100.0,
+            latency_ms: Some(60_000),
+            error_message: None,
+            status: None,
+            truncate_body_bytes: None,
+            close_after_messages: None,
+        });
+
+        let server_endpoint_url = Arc::new("ws://localhost/api/graphql-ws".to_string());
+        let message_capture = WsMessageCapture::new(
+            ConnectionId::new(),
+            MessageSender::new(0),
+            server_endpoint_url.clone(),
+        );
+        let (server_to_client_sender, mut server_to_client_receiver) = mpsc::unbounded_channel();
+        let (client_to_server_sender, mut client_to_server_receiver) = mpsc::unbounded_channel();
+        let connection_guard = admin_state.ws_connections().register(
+            message_capture.connection_id.as_arc_string(),
+            None,
+            server_endpoint_url,
+            message_capture.clone(),
+            WsChannels::new(&server_to_client_sender, &client_to_server_sender),
+        );
+        let session = WsSession {
+            fault_injector: Arc::new(WsFaultInjector::new(&admin_state)),
+            admin_state,
+            message_capture,
+            operation_tracker: Arc::new(WsOperationTracker::new()),
+            connection: connection_guard.connection().clone(),
+            protocol_translation: None,
+        };
+
+        let (server_frame_sender, server_frame_receiver) = mpsc::unbounded_channel();
+        let (client_frame_sender, client_frame_receiver) = mpsc::unbounded_channel();
+        let forwarders = [
+            tokio::spawn(forward_server_frames(
+                session.clone(),
+                server_frame_receiver,
+                server_to_client_sender.clone(),
+                client_to_server_sender.clone(),
+            )),
+            tokio::spawn(forward_client_frames(
+                session,
+                client_frame_receiver,
+                client_to_server_sender,
+                server_to_client_sender,
+            )),
+        ];
+
+        let pong = AxumWsMessage::Text(r#"{"type":"pong"}"#.to_string());
+        let ping = AxumWsMessage::Text(r#"{"type":"ping"}"#.to_string());
+        server_frame_sender.send(pong).unwrap();
+        client_frame_sender.send(ping.clone()).unwrap();
+
+        let client_message =
+            tokio::time::timeout(Duration::from_secs(1), client_to_server_receiver.recv()).await;
+        assert_eq!(client_message.ok().flatten(), Some(ping));
+        assert!(server_to_client_receiver.try_recv().is_err());
+
+        for forwarder in forwarders {
+            forwarder.abort();
+        }
+    }
+}
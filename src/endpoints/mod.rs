@@ -1,5 +1,12 @@
-mod graphql_proxy;
-mod graphql_ws_proxy;
+mod fault_injection;
+mod graphql_multipart_request;
+pub mod graphql_proxy;
+pub mod graphql_sse_proxy;
+mod graphql_ws_operation_tracker;
+mod graphql_ws_protocol;
+pub mod graphql_ws_proxy;
+mod multipart;
+pub mod response_body_capture;
 pub mod router;
 
 use async_graphql::http::GraphiQLSource;
@@ -0,0 +1,178 @@
+pub fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+pub fn multipart_boundary(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartPart {
+    pub headers: String,
+    pub body: Vec<u8>,
+}
+
+impl MultipartPart {
+    fn parse(part: &[u8]) -> Option<Self> {
+        let separator_position = part.windows(4).position(|window| window == b"\r\n\r\n")?;
+        let headers = String::from_utf8_lossy(&part[..separator_position])
+            .trim_start()
+            .to_string();
+        let body = &part[separator_position + 4..];
+
+        Some(Self {
+            headers,
+            body: body.to_vec(),
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.lines().find_map(|line| {
+            let (header_name, value) = line.split_once(':')?;
+            header_name
+                .trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim())
+        })
+    }
+
+    pub fn disposition_parameter(&self, name: &str) -> Option<String> {
+        self.header("content-disposition")?
+            .split(';')
+            .skip(1)
+            .find_map(|parameter| {
+                let (parameter_name, value) = parameter.split_once('=')?;
+                parameter_name
+                    .trim()
+                    .eq_ignore_ascii_case(name)
+                    .then(|| value.trim().trim_matches('"').to_string())
+            })
+    }
+}
+
+// a delimiter is a boundary at the start of a line, so it is not matched inside a part
+#[derive(Debug)]
+pub struct MultipartParts {
+    boundary: String,
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    scan_position: usize,
+    is_preamble: bool,
+}
+
+impl MultipartParts {
+    pub fn new(boundary: &str) -> Self {
+        Self {
+            boundary: boundary.to_string(),
+            // the first delimiter may start the body, so it is not preceded by a line break
+            delimiter: format!("--{boundary}").into_bytes(),
+            buffer: Vec::new(),
+            scan_position: 0,
+            is_preamble: true,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<MultipartPart> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut parts = Vec::new();
+        while let Some(position) = self.buffer[self.scan_position..]
+            .windows(self.delimiter.len())
+            .position(|window| window == self.delimiter.as_slice())
+            .map(|position| self.scan_position + position)
+        {
+            let part = self
+                .buffer
+                .drain(..position + self.delimiter.len())
+                .collect::<Vec<u8>>();
+            self.scan_position = 0;
+
+            if std::mem::replace(&mut self.is_preamble, false) {
+                self.delimiter = format!("\r\n--{}", self.boundary).into_bytes();
+                continue;
+            }
+
+            parts.extend(MultipartPart::parse(&part[..position]));
+        }
+
+        // a delimiter may start in the last bytes and end in the next chunk
+        self.scan_position = self.buffer.len().saturating_sub(self.delimiter.len() - 1);
+
+        parts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_parameters() {
+        assert_eq!(
+            media_type("Multipart/Form-Data; boundary=foo"),
+            "multipart/form-data"
+        );
+        assert_eq!(
+            multipart_boundary(r#"multipart/mixed; boundary="graphql"; deferSpec=20220824"#),
+            Some("graphql".to_string())
+        );
+        assert_eq!(multipart_boundary("multipart/mixed"), None);
+    }
+
+    #[test]
+    fn test_multipart_parts_boundary_in_content() {
+        let mut parts = MultipartParts::new("foo");
+
+        assert_eq!(
+            parts.push(b"--foo\r\nContent-Type: text/plain\r\n\r\nbar--foo\r\n\r\n--foo--\r\n"),
+            vec![MultipartPart {
+                headers: "Content-Type: text/plain".to_string(),
+                body: b"bar--foo\r\n".to_vec(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_multipart_parts() {
+        let mut parts = MultipartParts::new("foo");
+
+        assert_eq!(
+            parts.push(b"preamble\r\n--foo\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nbar\r\n--f"),
+            vec![]
+        );
+
+        let completed_parts =
+            parts.push(b"oo\r\nContent-Type: text/plain\r\n\r\nbaz\r\n--foo--\r\n");
+        assert_eq!(
+            completed_parts,
+            vec![
+                MultipartPart {
+                    headers: "Content-Disposition: form-data; name=\"a\"".to_string(),
+                    body: b"bar".to_vec(),
+                },
+                MultipartPart {
+                    headers: "Content-Type: text/plain".to_string(),
+                    body: b"baz".to_vec(),
+                },
+            ]
+        );
+        assert_eq!(
+            completed_parts[0].disposition_parameter("name"),
+            Some("a".to_string())
+        );
+        assert_eq!(
+            completed_parts[1].header("content-type"),
+            Some("text/plain")
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use super::multipart::{media_type, multipart_boundary, MultipartParts};
+
+const DEFAULT_MULTIPART_BOUNDARY: &str = "-";
+
+// the bytes of a body captured as a whole, the rest of the body is still forwarded
+const CAPTURED_BODY_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum ResponseBodyCapture {
+    Whole(Vec<u8>),
+    Multipart(MultipartParts),
+    EventStream(Vec<u8>),
+}
+
+impl ResponseBodyCapture {
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type.map(|content_type| (media_type(content_type), content_type)) {
+            Some((media_type, content_type)) if media_type.starts_with("multipart/") => {
+                Self::Multipart(MultipartParts::new(
+                    &multipart_boundary(content_type)
+                        .unwrap_or_else(|| DEFAULT_MULTIPART_BOUNDARY.to_string()),
+                ))
+            }
+            Some((media_type, _)) if media_type == "text/event-stream" => {
+                Self::EventStream(Vec::new())
+            }
+            _ => Self::Whole(Vec::new()),
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        match self {
+            Self::Whole(body) => {
+                let size = chunk.len().min(CAPTURED_BODY_MAX_SIZE - body.len());
+                if size < chunk.len() && body.len() < CAPTURED_BODY_MAX_SIZE {
+                    log::warn!(
+                        "the captured response body is truncated to {CAPTURED_BODY_MAX_SIZE} bytes"
+                    );
+                }
+                body.extend_from_slice(&chunk[..size]);
+                Vec::new()
+            }
+            Self::EventStream(buffer) => push_event_stream(buffer, chunk),
+            Self::Multipart(parts) => parts
+                .push(chunk)
+                .into_iter()
+                .filter_map(|part| {
+                    let text = String::from_utf8_lossy(&part.body).trim().to_string();
+                    (!text.is_empty()).then_some(text)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn finish(self) -> Vec<String> {
+        match self {
+            Self::Whole(body) => vec![String::from_utf8_lossy(&body).into_owned()],
+            Self::Multipart(_parts) => Vec::new(),
+            Self::EventStream(mut buffer) => push_event_stream(&mut buffer, b"\n\n"),
+        }
+    }
+}
+
+pub fn frame_captured_texts<'a>(
+    content_type: Option<&str>,
+    texts: impl IntoIterator<Item = &'a str>,
+) -> String {
+    match content_type.map(|content_type| (media_type(content_type), content_type)) {
+        Some((media_type, content_type)) if media_type.starts_with("multipart/") => {
+            let boundary = multipart_boundary(content_type)
+                .unwrap_or_else(|| DEFAULT_MULTIPART_BOUNDARY.to_string());
+            let mut body = texts
+                .into_iter()
+                .map(|text| {
+                    format!(
+                        "\r\n--{boundary}\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{text}"
+                    )
+                })
+                .collect::<String>();
+            body.push_str(&format!("\r\n--{boundary}--\r\n"));
+            body
+        }
+        Some((media_type, _)) if media_type == "text/event-stream" => {
+            let mut body = texts
+                .into_iter()
+                .map(|text| format!("event: next\ndata: {}\n\n", text.replace('\n', "\ndata: ")))
+                .collect::<String>();
+            body.push_str("event: complete\ndata:\n\n");
+            body
+        }
+        _ => texts.into_iter().collect(),
+    }
+}
+
+// the carriage returns are dropped, so the lines of the stream end with a line feed
+fn push_event_stream(buffer: &mut Vec<u8>, chunk: &[u8]) -> Vec<String> {
+    // an event that ends with the chunk may start in the buffered bytes
+    let scan_position = buffer.len().saturating_sub(1);
+    buffer.extend(chunk.iter().filter(|byte| **byte != b'\r'));
+
+    let Some(position) = buffer[scan_position..]
+        .windows(2)
+        .rposition(|window| window == b"\n\n")
+        .map(|position| scan_position + position)
+    else {
+        return Vec::new();
+    };
+
+    let events = buffer.drain(..position + 2).collect::<Vec<u8>>();
+    String::from_utf8_lossy(&events)
+        .split("\n\n")
+        .filter_map(|event| {
+            let data = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect::<Vec<_>>()
+                .join("\n");
+            (!data.trim().is_empty()).then_some(data)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_body_capture_whole() {
+        let mut body_capture = ResponseBodyCapture::from_content_type(Some("application/json"));
+
+        assert_eq!(body_capture.push(br#"{"data":"#), Vec::<String>::new());
+        assert_eq!(body_capture.push(br#"{"foo":1}}"#), Vec::<String>::new());
+        assert_eq!(
+            body_capture.finish(),
+            vec![r#"{"data":{"foo":1}}"#.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_response_body_capture_event_stream() {
+        let mut body_capture = ResponseBodyCapture::from_content_type(Some("text/event-stream"));
+
+        assert_eq!(
+            body_capture.push(b"event: next\r\ndata: {\"data\":\r\ndata: {\"foo\":1}}\r\n\r"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            body_capture.push(b"\n: heartbeat\n\nevent: complete\ndata:\n\nevent: next\n"),
+            vec!["{\"data\":\n{\"foo\":1}}".to_string()]
+        );
+        assert_eq!(
+            body_capture.push(b"data: {\"bar\":2}"),
+            Vec::<String>::new()
+        );
+        assert_eq!(body_capture.finish(), vec![r#"{"bar":2}"#.to_string()]);
+    }
+
+    #[test]
+    fn test_response_body_capture_multipart() {
+        let mut body_capture = ResponseBodyCapture::from_content_type(Some(
+            r#"multipart/mixed; boundary="graphql"; deferSpec=20220824"#,
+        ));
+
+        assert_eq!(
+            body_capture.push(
+                b"\r\n--graphql\r\nContent-Type: application/json\r\n\r\n{\"data\":{\"foo\":1},\"hasNext\":true}\r\n--gra"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            body_capture.push(b"phql\r\nContent-Type: application/json\r\n\r\n{\"hasNext\":false}"),
+            vec![r#"{"data":{"foo":1},"hasNext":true}"#.to_string()]
+        );
+        assert_eq!(
+            body_capture.push(b"\r\n--graphql--\r\n"),
+            vec![r#"{"hasNext":false}"#.to_string()]
+        );
+        assert_eq!(body_capture.finish(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_response_body_capture_default_boundary() {
+        let mut body_capture = ResponseBodyCapture::from_content_type(Some("multipart/mixed"));
+
+        assert_eq!(
+            body_capture.push(b"---\r\n\r\n{\"foo\":1}\r\n---\r\n\r\n{\"bar\":2}\r\n-----\r\n"),
+            vec![r#"{"foo":1}"#.to_string(), r#"{"bar":2}"#.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_frame_captured_texts() {
+        let texts = [
+            r#"{"data":{"foo":1},"hasNext":true}"#,
+            r#"{"hasNext":false}"#,
+        ];
+
+        for content_type in [
+            r#"multipart/mixed; boundary="graphql""#,
+            "multipart/mixed",
+            "text/event-stream",
+        ] {
+            let body = frame_captured_texts(Some(content_type), texts);
+            let mut body_capture = ResponseBodyCapture::from_content_type(Some(content_type));
+
+            let mut captured_texts = body_capture.push(body.as_bytes());
+            captured_texts.extend(body_capture.finish());
+            assert_eq!(captured_texts, texts);
+        }
+
+        assert_eq!(
+            frame_captured_texts(Some("application/json"), [r#"{"data":null}"#]),
+            r#"{"data":null}"#
+        );
+    }
+}
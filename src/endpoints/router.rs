@@ -1,6 +1,6 @@
 use std::convert::Infallible;
 
-use async_graphql::Schema;
+use async_graphql::{dynamic, Schema};
 use async_graphql_axum::{GraphQL, GraphQLSubscription};
 use axum::{
     routing::{get, post, post_service},
@@ -9,7 +9,7 @@ use axum::{
 use axum_helpers::response_http_header_mutator::ResponseHttpHeaderMutatorLayer;
 use config::{
     ADMIN_GRAPHQL_ENDPOINT, ADMIN_GRAPHQL_WS_ENDPOINT, PROXIED_GRAPHQL_ENDPOINT,
-    PROXIED_GRAPHQL_WS_ENDPOINT,
+    PROXIED_GRAPHQL_SSE_ENDPOINT, PROXIED_GRAPHQL_WS_ENDPOINT,
 };
 use tower_http::trace::TraceLayer;
 
@@ -19,7 +19,10 @@ use crate::{
 };
 
 use super::{
-    get_index, graphiql, graphql_proxy::post_graphql_proxy, graphql_ws_proxy::get_graphql_ws_proxy,
+    get_index, graphiql,
+    graphql_proxy::{get_graphql_proxy, post_graphql_proxy},
+    graphql_sse_proxy::post_graphql_sse_proxy,
+    graphql_ws_proxy::get_graphql_ws_proxy,
     options_graphql,
 };
 
@@ -29,6 +32,24 @@ mod config {
 
     pub const PROXIED_GRAPHQL_ENDPOINT: &str = "/api/graphql";
     pub const PROXIED_GRAPHQL_WS_ENDPOINT: &str = "/api/graphql-ws";
+    pub const PROXIED_GRAPHQL_SSE_ENDPOINT: &str = "/api/graphql-sse";
+}
+
+pub fn mock_server_routes(schema: dynamic::Schema) -> Router {
+    Router::new()
+        .route(
+            "/graphiql",
+            get(|| graphiql(PROXIED_GRAPHQL_ENDPOINT, PROXIED_GRAPHQL_WS_ENDPOINT)),
+        )
+        .route_service(
+            PROXIED_GRAPHQL_WS_ENDPOINT,
+            GraphQLSubscription::new(schema.clone()),
+        )
+        .route(
+            PROXIED_GRAPHQL_ENDPOINT,
+            post_service(GraphQL::new(schema)).options(options_graphql),
+        )
+        .layer(TraceLayer::new_for_http())
 }
 
 pub fn routes(state: AppState, schema: Schema<Query, Mutation, Subscription>) -> Router {
@@ -71,9 +92,16 @@ pub fn routes(state: AppState, schema: Schema<Query, Mutation, Subscription>) ->
             get(|| graphiql(PROXIED_GRAPHQL_ENDPOINT, PROXIED_GRAPHQL_WS_ENDPOINT)),
         )
         .route(PROXIED_GRAPHQL_WS_ENDPOINT, get(get_graphql_ws_proxy))
+        .route(
+            PROXIED_GRAPHQL_SSE_ENDPOINT,
+            post(post_graphql_sse_proxy)
+                .options(options_graphql)
+                .route_layer(preflight_middleware.clone()),
+        )
         .route(
             PROXIED_GRAPHQL_ENDPOINT,
             post(post_graphql_proxy)
+                .get(get_graphql_proxy)
                 .options(options_graphql)
                 .route_layer(preflight_middleware.clone()),
         );
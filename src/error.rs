@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 #[derive(Clone, Debug, thiserror::Error)]
 #[error("UnimplementedError: '{0}'")]
 pub struct UnimplementedError(pub String);
@@ -17,3 +19,563 @@ pub struct CannotParseBoolFromEnvVarError {
     #[source]
     pub source: std::str::ParseBoolError,
 }
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotCreateCaptureDirectoryError, path = '{path}'")]
+pub struct CannotCreateCaptureDirectoryError {
+    pub path: PathBuf,
+    #[source]
+    pub source: std::io::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotWriteCaptureFileError, path = '{path}'")]
+pub struct CannotWriteCaptureFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: std::io::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotReadCaptureFileError, path = '{path}'")]
+pub struct CannotReadCaptureFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: std::io::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotParseCaptureFileError, path = '{path}', line = {line}")]
+pub struct CannotParseCaptureFileError {
+    pub path: PathBuf,
+    pub line: usize,
+    #[source]
+    pub source: serde_json::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadCaptureError {
+    #[error(transparent)]
+    CannotRead(#[from] CannotReadCaptureFileError),
+    #[error(transparent)]
+    CannotParse(#[from] CannotParseCaptureFileError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotReadSdlFileError, path = '{path}'")]
+pub struct CannotReadSdlFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: std::io::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotParseSdlError")]
+pub struct CannotParseSdlError {
+    #[source]
+    pub source: async_graphql_parser::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotBuildMockSchemaError")]
+pub struct CannotBuildMockSchemaError {
+    #[source]
+    pub source: async_graphql::dynamic::SchemaError,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotReadMockOverrideFileError, path = '{path}'")]
+pub struct CannotReadMockOverrideFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: std::io::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotParseMockOverrideFileError, path = '{path}'")]
+pub struct CannotParseMockOverrideFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: serde_json::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadMockSchemaError {
+    #[error(transparent)]
+    ReadSdlFile(#[from] CannotReadSdlFileError),
+    #[error(transparent)]
+    ParseSdl(#[from] CannotParseSdlError),
+    #[error(transparent)]
+    BuildSchema(#[from] CannotBuildMockSchemaError),
+    #[error(transparent)]
+    ReadOverrideFile(#[from] CannotReadMockOverrideFileError),
+    #[error(transparent)]
+    ParseOverrideFile(#[from] CannotParseMockOverrideFileError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("MissingMultipartBoundaryError")]
+pub struct MissingMultipartBoundaryError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("MissingMultipartFieldError, field = '{field}'")]
+pub struct MissingMultipartFieldError {
+    pub field: &'static str,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotParseMultipartFieldError, field = '{field}'")]
+pub struct CannotParseMultipartFieldError {
+    pub field: &'static str,
+    #[source]
+    pub source: serde_json::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseMultipartRequestError {
+    #[error(transparent)]
+    MissingBoundary(#[from] MissingMultipartBoundaryError),
+    #[error(transparent)]
+    MissingField(#[from] MissingMultipartFieldError),
+    #[error(transparent)]
+    ParseField(#[from] CannotParseMultipartFieldError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotReadConfigFileError, path = '{path}'")]
+pub struct CannotReadConfigFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: std::io::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotParseConfigFileError, path = '{path}'")]
+pub struct CannotParseConfigFileError {
+    pub path: PathBuf,
+    #[source]
+    pub source: toml::de::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("InvalidConfigHeaderError, path = '{path}', name = '{name}'")]
+pub struct InvalidConfigHeaderError {
+    pub path: PathBuf,
+    pub name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadConfigFileError {
+    #[error(transparent)]
+    CannotRead(#[from] CannotReadConfigFileError),
+    #[error(transparent)]
+    CannotParse(#[from] CannotParseConfigFileError),
+    #[error(transparent)]
+    InvalidHeader(#[from] InvalidConfigHeaderError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateAppStateError {
+    #[error(transparent)]
+    HttpClient(#[from] reqwest::Error),
+    #[error(transparent)]
+    WsConnector(#[from] rustls::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("UnspecifiedListenerAddressError")]
+pub struct UnspecifiedListenerAddressError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("CannotParseStateSnapshotError")]
+pub struct CannotParseStateSnapshotError {
+    #[source]
+    pub source: serde_json::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("UnsupportedStateSnapshotVersionError, version = {version}")]
+pub struct UnsupportedStateSnapshotVersionError {
+    pub version: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("InvalidStateSnapshotHeaderError, name = '{name}'")]
+pub struct InvalidStateSnapshotHeaderError {
+    pub name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestoreStateSnapshotError {
+    #[error(transparent)]
+    CannotParse(#[from] CannotParseStateSnapshotError),
+    #[error(transparent)]
+    UnsupportedVersion(#[from] UnsupportedStateSnapshotVersionError),
+    #[error(transparent)]
+    InvalidHeader(#[from] InvalidStateSnapshotHeaderError),
+    #[error(transparent)]
+    InvalidFaultRulePercentage(#[from] InvalidFaultRulePercentageError),
+    #[error(transparent)]
+    UnknownUpstreamProfile(#[from] UnknownUpstreamProfileError),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "InvalidFaultRulePercentageError, percentage = {percentage}, it has to be between 0 and 100"
+)]
+pub struct InvalidFaultRulePercentageError {
+    pub percentage: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "InvalidCloseCodeError, code = {code}, it has to be between 1000 and 1003, 1007 and 1014 or 3000 and 4999"
+)]
+pub struct InvalidCloseCodeError {
+    pub code: u16,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("UnknownUpstreamProfileError, name = '{name}'")]
+pub struct UnknownUpstreamProfileError {
+    pub name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("UpstreamProfileAlreadyExistsError, name = '{name}'")]
+pub struct UpstreamProfileAlreadyExistsError {
+    pub name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("AdminApiError, errors = {errors}")]
+pub struct AdminApiError {
+    pub errors: serde_json::Value,
+}
+
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyStage {
+    ClientRequest,
+    Policy,
+    Cassette,
+    UpstreamConnect,
+    UpstreamResponse,
+}
+
+impl ProxyStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ClientRequest => "CLIENT_REQUEST",
+            Self::Policy => "POLICY",
+            Self::Cassette => "CASSETTE",
+            Self::UpstreamConnect => "UPSTREAM_CONNECT",
+            Self::UpstreamResponse => "UPSTREAM_RESPONSE",
+        }
+    }
+}
+
+impl std::fmt::Display for ProxyStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProxyError {
+    #[error("InvalidUpstreamUrlError, location = '{location}'")]
+    InvalidUpstreamUrl {
+        location: String,
+        #[source]
+        source: BoxedError,
+    },
+    #[error("UpstreamConnectError, location = '{location}'")]
+    UpstreamConnect {
+        location: String,
+        #[source]
+        source: BoxedError,
+    },
+    #[error("UpstreamTlsError, location = '{location}'")]
+    UpstreamTls {
+        location: String,
+        #[source]
+        source: BoxedError,
+    },
+    #[error("UpstreamTimeoutError, location = '{location}', stage = '{stage}'")]
+    UpstreamTimeout {
+        location: String,
+        stage: ProxyStage,
+        #[source]
+        source: BoxedError,
+    },
+    #[error("UpstreamError, location = '{location}'")]
+    Upstream {
+        location: String,
+        #[source]
+        source: BoxedError,
+    },
+    #[error("ParseError, location = '{location}'")]
+    Parse {
+        location: String,
+        #[source]
+        source: BoxedError,
+    },
+    #[error("PayloadTooLargeError, location = '{location}', max size = {max_size}")]
+    PayloadTooLarge { location: String, max_size: usize },
+    #[error("PolicyRejectionError, location = '{location}', reason = '{reason}'")]
+    PolicyRejection {
+        location: String,
+        reason: &'static str,
+    },
+    #[error("MissingRecordedResponseError, location = '{location}'")]
+    MissingRecordedResponse { location: String },
+}
+
+impl ProxyError {
+    pub fn parse(location: String, source: impl Into<BoxedError>) -> Self {
+        Self::Parse {
+            location,
+            source: source.into(),
+        }
+    }
+
+    pub fn policy_rejection(location: String, reason: &'static str) -> Self {
+        Self::PolicyRejection { location, reason }
+    }
+
+    pub fn upstream(location: String, source: impl Into<BoxedError>) -> Self {
+        Self::Upstream {
+            location,
+            source: source.into(),
+        }
+    }
+
+    // reqwest does not tell tls failures apart from the other connect failures, so they are
+    // recognized by the messages of the underlying errors
+    pub fn from_reqwest_error(location: String, error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::UpstreamTimeout {
+                location,
+                stage: if error.is_connect() {
+                    ProxyStage::UpstreamConnect
+                } else {
+                    ProxyStage::UpstreamResponse
+                },
+                source: error.into(),
+            }
+        } else if error.is_builder() {
+            Self::InvalidUpstreamUrl {
+                location,
+                source: error.into(),
+            }
+        } else if error.is_connect() && is_tls_error(&error) {
+            Self::UpstreamTls {
+                location,
+                source: error.into(),
+            }
+        } else if error.is_connect() {
+            Self::UpstreamConnect {
+                location,
+                source: error.into(),
+            }
+        } else {
+            Self::upstream(location, error)
+        }
+    }
+
+    pub fn from_tungstenite_error(
+        location: String,
+        error: tokio_tungstenite::tungstenite::Error,
+    ) -> Self {
+        use tokio_tungstenite::tungstenite::Error;
+
+        match error {
+            Error::Url(_) => Self::InvalidUpstreamUrl {
+                location,
+                source: error.into(),
+            },
+            Error::Tls(_) => Self::UpstreamTls {
+                location,
+                source: error.into(),
+            },
+            Error::Io(ref io_error) if io_error.kind() == std::io::ErrorKind::TimedOut => {
+                Self::UpstreamTimeout {
+                    location,
+                    stage: ProxyStage::UpstreamConnect,
+                    source: error.into(),
+                }
+            }
+            Error::Io(_) | Error::Http(_) | Error::HttpFormat(_) => Self::UpstreamConnect {
+                location,
+                source: error.into(),
+            },
+            error => Self::upstream(location, error),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidUpstreamUrl { .. } => "INVALID_UPSTREAM_URL",
+            Self::UpstreamConnect { .. } => "UPSTREAM_CONNECT_FAILED",
+            Self::UpstreamTls { .. } => "UPSTREAM_TLS_FAILED",
+            Self::UpstreamTimeout { .. } => "UPSTREAM_TIMEOUT",
+            Self::Upstream { .. } => "UPSTREAM_FAILED",
+            Self::Parse { .. } => "PARSE_FAILED",
+            Self::PayloadTooLarge { .. } => "PAYLOAD_TOO_LARGE",
+            Self::PolicyRejection { .. } => "POLICY_REJECTED",
+            Self::MissingRecordedResponse { .. } => "MISSING_RECORDED_RESPONSE",
+        }
+    }
+
+    pub fn stage(&self) -> ProxyStage {
+        match self {
+            Self::InvalidUpstreamUrl { .. }
+            | Self::UpstreamConnect { .. }
+            | Self::UpstreamTls { .. } => ProxyStage::UpstreamConnect,
+            Self::UpstreamTimeout { stage, .. } => *stage,
+            Self::Upstream { .. } => ProxyStage::UpstreamResponse,
+            Self::Parse { .. } | Self::PayloadTooLarge { .. } => ProxyStage::ClientRequest,
+            Self::PolicyRejection { .. } => ProxyStage::Policy,
+            Self::MissingRecordedResponse { .. } => ProxyStage::Cassette,
+        }
+    }
+
+    // the errors of the request and the policy are answered with the graphql errors of a
+    // successful response
+    pub fn status(&self) -> http::StatusCode {
+        use tokio_tungstenite::tungstenite::Error;
+
+        match self {
+            Self::UpstreamTimeout { .. } => http::StatusCode::GATEWAY_TIMEOUT,
+            Self::UpstreamConnect { source, .. } => match source.downcast_ref::<Error>() {
+                Some(Error::Http(response)) => response.status(),
+                _ => http::StatusCode::BAD_GATEWAY,
+            },
+            Self::InvalidUpstreamUrl { .. } | Self::UpstreamTls { .. } | Self::Upstream { .. } => {
+                http::StatusCode::BAD_GATEWAY
+            }
+            Self::PayloadTooLarge { .. } => http::StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Parse { .. }
+            | Self::PolicyRejection { .. }
+            | Self::MissingRecordedResponse { .. } => http::StatusCode::OK,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            message.push_str(&format!(", caused by = '{error}'"));
+            source = error.source();
+        }
+
+        message
+    }
+
+    pub fn to_server_error(&self) -> async_graphql::ServerError {
+        let mut extensions = async_graphql::ErrorExtensionValues::default();
+        extensions.set("code", self.code());
+        extensions.set("proxyStage", self.stage().as_str());
+
+        let mut server_error = async_graphql::ServerError::new(self.message(), None);
+        server_error.extensions = Some(extensions);
+
+        server_error
+    }
+}
+
+impl From<ProxyError> for async_graphql_axum::GraphQLResponse {
+    fn from(error: ProxyError) -> Self {
+        log::error!("{}", error.message());
+
+        Self::from(async_graphql::Response::from_errors(vec![
+            error.to_server_error()
+        ]))
+    }
+}
+
+impl axum::response::IntoResponse for ProxyError {
+    fn into_response(self) -> axum::response::Response {
+        let status = self.status();
+        (status, async_graphql_axum::GraphQLResponse::from(self)).into_response()
+    }
+}
+
+// the error itself is skipped, its message contains the url of the server
+fn is_tls_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = error.source();
+    while let Some(error) = source {
+        let message = error.to_string().to_ascii_lowercase();
+        if message.contains("certificate") || message.contains("tls") || message.contains("ssl") {
+            return true;
+        }
+        source = error.source();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_error_extensions() {
+        let error = ProxyError::parse(
+            "src/foo.rs:1".to_string(),
+            serde_json::from_str::<serde_json::Value>("{").unwrap_err(),
+        );
+        let server_error = serde_json::json!(error.to_server_error());
+
+        assert!(server_error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("ParseError, location = 'src/foo.rs:1', caused by = 'EOF"));
+        assert_eq!(server_error["extensions"]["code"], "PARSE_FAILED");
+        assert_eq!(server_error["extensions"]["proxyStage"], "CLIENT_REQUEST");
+
+        let error = ProxyError::policy_rejection(
+            "src/foo.rs:2".to_string(),
+            "Mutations are set to be prohibited",
+        );
+
+        assert_eq!(error.code(), "POLICY_REJECTED");
+        assert_eq!(error.stage(), ProxyStage::Policy);
+        assert_eq!(error.status(), http::StatusCode::OK);
+    }
+
+    #[test]
+    fn test_proxy_error_status() {
+        use tokio_tungstenite::tungstenite::Error;
+
+        let error = ProxyError::from_tungstenite_error(
+            "src/foo.rs:1".to_string(),
+            Error::Http(
+                http::Response::builder()
+                    .status(http::StatusCode::UNAUTHORIZED)
+                    .body(None)
+                    .unwrap(),
+            ),
+        );
+        assert_eq!(error.status(), http::StatusCode::UNAUTHORIZED);
+
+        let error = ProxyError::from_tungstenite_error(
+            "src/foo.rs:2".to_string(),
+            Error::Io(std::io::ErrorKind::TimedOut.into()),
+        );
+        assert_eq!(error.status(), http::StatusCode::GATEWAY_TIMEOUT);
+
+        let error = ProxyError::from_tungstenite_error(
+            "src/foo.rs:3".to_string(),
+            Error::Io(std::io::ErrorKind::ConnectionRefused.into()),
+        );
+        assert_eq!(error.status(), http::StatusCode::BAD_GATEWAY);
+
+        let error = ProxyError::PayloadTooLarge {
+            location: "src/foo.rs:4".to_string(),
+            max_size: 1,
+        };
+        assert_eq!(error.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
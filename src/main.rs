@@ -6,12 +6,23 @@
 
 mod admin_state;
 mod app_state;
+mod breakpoints;
+mod capture;
 mod cli;
+mod cli_capture;
+mod cli_mock_server;
 mod cli_query;
+mod cli_state;
+mod config_file;
 mod endpoints;
 mod error;
+mod message_sender;
+mod mock_server;
 mod model;
+mod state_snapshot;
 mod utils;
+mod ws_connections;
+mod ws_tls;
 
 use std::net::ToSocketAddrs;
 
@@ -19,18 +30,28 @@ use admin_state::AdminState;
 use app_state::AppState;
 use async_graphql::{SDLExportOptions, Schema};
 use axum_helpers::app::AxumApp;
+use capture::{
+    ndjson_reader::load_captured_messages,
+    ndjson_writer::{spawn_ndjson_capture, NdjsonCaptureConfig},
+    replay::Cassette,
+};
 use clap::Parser;
 use cli::{Cli, Command};
+use cli_capture::export_har;
+use cli_mock_server::serve_mock_server;
 use cli_query::{execute_cli_query, subscribe_to_messages};
+use cli_state::execute_state_command;
+use config_file::{spawn_config_file_watcher, ConfigFile, ProxySettings};
 use endpoints::router::routes;
 use error::{
     CannotParseBoolFromEnvVarError, UnspecifiedGraphQLEndpointError,
-    UnspecifiedGraphQLWsEndpointError,
+    UnspecifiedGraphQLWsEndpointError, UnspecifiedListenerAddressError,
 };
 use http::HeaderMap;
 use model::{mutation::Mutation, query::Query, subscription::Subscription};
 
-fn create_admin_schema(admin_state: AdminState) -> Schema<Query, Mutation, Subscription> {
+fn create_admin_schema(app_state: AppState) -> Schema<Query, Mutation, Subscription> {
+    let admin_state = app_state.admin_state();
     let query = Query {
         admin_state: admin_state.clone(),
     };
@@ -40,17 +61,18 @@ fn create_admin_schema(admin_state: AdminState) -> Schema<Query, Mutation, Subsc
     let subscription: Subscription = Subscription {
         admin_state: admin_state.clone(),
     };
-    Schema::build(query, mutation, subscription).finish()
+    Schema::build(query, mutation, subscription)
+        .data(app_state)
+        .finish()
 }
 
 async fn serve(
-    schema: Schema<Query, Mutation, Subscription>,
-    admin_state: AdminState,
+    app_state: AppState,
     listener_address: impl AsRef<str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     log::info!("starting application in server mode");
 
-    let app_state = AppState::new(admin_state)?;
+    let schema = create_admin_schema(app_state.clone());
 
     let mut app = AxumApp::new(routes(app_state, schema));
 
@@ -89,18 +111,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     match cli.command {
         Command::Serve(params) => {
+            let config_file = params
+                .config
+                .as_deref()
+                .map(ConfigFile::load)
+                .transpose()?
+                .unwrap_or_default();
+            let command_line_settings = ProxySettings {
+                server_graphql_endpoint: params.server_graphql_endpoint,
+                server_graphql_ws_endpoint: params.server_graphql_ws_endpoint,
+                prohibit_mutation: params.prohibit_mutation.then_some(true),
+                request_headers: params.request_headers.into_iter().collect(),
+                response_headers: params.response_headers.into_iter().collect(),
+            };
+            let settings = command_line_settings.or(config_file.settings.clone());
+            let listener_address = params
+                .listener_address
+                .or_else(|| config_file.listener_address.clone())
+                .ok_or(UnspecifiedListenerAddressError)?;
+
+            let cassette = if params.replay.is_empty() {
+                None
+            } else {
+                log::info!("serving responses from the recording, no server is contacted");
+                Some(Cassette::new(
+                    load_captured_messages(&params.replay)?,
+                    params.replay_match,
+                ))
+            };
+
+            let is_replaying = cassette.is_some();
             let admin_state = AdminState::new(
-                params
+                settings
                     .server_graphql_endpoint
                     .or_else(|| std::env::var(DEFAULT_SERVER_GRAPHQL_ENDPOINT_ENV_VARNAME).ok())
+                    .or_else(|| is_replaying.then(String::new))
                     .ok_or(UnspecifiedGraphQLEndpointError)?,
-                params
+                settings
                     .server_graphql_ws_endpoint
                     .or_else(|| std::env::var(DEFAULT_SERVER_GRAPHQL_WS_ENDPOINT_ENV_VARNAME).ok())
+                    .or_else(|| is_replaying.then(String::new))
                     .ok_or(UnspecifiedGraphQLWsEndpointError)?,
-                params
+                settings
                     .prohibit_mutation
-                    .then_some(Ok(true))
+                    .map(Ok)
                     .or_else(|| {
                         std::env::var(DEFAULT_PROHIBIT_MUTATION_ENV_VARNAME)
                             .ok()
@@ -115,21 +169,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     })
                     .transpose()?
                     .unwrap_or(false),
-                params.request_headers.into_iter().collect(),
-                params.response_headers.into_iter().collect(),
+                settings.request_headers,
+                settings.response_headers,
+                params.message_history_size,
+            );
+
+            let breakpoint_timeout = std::time::Duration::from(params.breakpoint_timeout);
+            admin_state.breakpoints().set_auto_resume_timeout(
+                (!breakpoint_timeout.is_zero()).then_some(breakpoint_timeout),
             );
 
-            let schema = create_admin_schema(admin_state.clone());
-            serve(schema, admin_state, params.listener_address).await?
+            if let Some(config) = params.config {
+                spawn_config_file_watcher(
+                    admin_state.clone(),
+                    config,
+                    config_file,
+                    command_line_settings,
+                );
+            }
+
+            if let Some(capture_dir) = params.capture_dir {
+                spawn_ndjson_capture(
+                    admin_state.message_sender_ref(),
+                    NdjsonCaptureConfig {
+                        directory: capture_dir,
+                        max_file_size: params.capture_max_file_size,
+                        rotation_interval: params.capture_rotation_interval.into(),
+                        queue_size: params.capture_queue_size,
+                        redact_sensitive_headers: !params.capture_sensitive_headers,
+                    },
+                )?;
+            }
+
+            serve(
+                AppState::new(admin_state, cassette, params.max_upload_size)?,
+                listener_address,
+            )
+            .await?
         }
+        Command::MockServer(params) => serve_mock_server(params).await?,
         Command::Sdl => {
-            let schema = create_admin_schema(AdminState::new(
-                "",
-                "",
-                false,
-                HeaderMap::default(),
-                HeaderMap::default(),
-            ));
+            let schema = create_admin_schema(AppState::new(
+                AdminState::new("", "", false, HeaderMap::default(), HeaderMap::default(), 0),
+                None,
+                0,
+            )?);
             println!(
                 "{}",
                 schema.sdl_with_options(SDLExportOptions::new().prefer_single_line_descriptions())
@@ -137,6 +221,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Command::Query(params) => execute_cli_query(params).await?,
         Command::SubscribeToMessages(params) => subscribe_to_messages(params).await?,
+        Command::ExportHar(params) => export_har(params)?,
+        Command::State(command) => execute_state_command(command).await?,
     }
 
     Ok(())
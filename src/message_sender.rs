@@ -0,0 +1,199 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use parking_lot::{Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+use crate::model::types::message::Message;
+
+pub struct MessageHistory {
+    capacity: usize,
+    next_event_id: u64,
+    messages: VecDeque<Message>,
+}
+
+impl MessageHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_event_id: 0,
+            messages: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn next_event_id(&self) -> u64 {
+        self.next_event_id
+    }
+
+    fn push(&mut self, message: &mut Message) {
+        message.event_id = self.next_event_id;
+        self.next_event_id += 1;
+
+        if self.capacity != 0 {
+            if self.messages.len() == self.capacity {
+                self.messages.pop_front();
+            }
+            self.messages.push_back(message.clone());
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.messages.iter()
+    }
+
+    pub fn iter_after(&self, after: Option<u64>) -> impl Iterator<Item = &Message> {
+        self.messages
+            .iter()
+            .filter(move |message| after.is_none_or(|after| message.event_id > after))
+    }
+
+    pub fn messages_from(&self, next_event_id: u64) -> (Vec<Message>, u64) {
+        let oldest_event_id = self
+            .messages
+            .front()
+            .map(|message| message.event_id)
+            .unwrap_or(self.next_event_id);
+
+        (
+            self.messages
+                .iter()
+                .filter(|message| message.event_id >= next_event_id)
+                .cloned()
+                .collect(),
+            oldest_event_id.saturating_sub(next_event_id),
+        )
+    }
+}
+
+struct MessageSenderInner {
+    broadcast_sender: broadcast::Sender<Message>,
+    history: Mutex<MessageHistory>,
+}
+
+#[derive(Clone)]
+pub struct MessageSender(Arc<MessageSenderInner>);
+
+impl MessageSender {
+    pub fn new(history_capacity: usize) -> Self {
+        Self(Arc::new(MessageSenderInner {
+            broadcast_sender: broadcast::channel(128).0,
+            history: Mutex::new(MessageHistory::new(history_capacity)),
+        }))
+    }
+
+    pub fn send(&self, mut message: Message) {
+        // the history lock is held while broadcasting, so the order of the history and the order
+        // of the broadcast channel are the same
+        let mut history = self.0.history.lock();
+        history.push(&mut message);
+        let _ = self.0.broadcast_sender.send(message);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Message> {
+        self.0.broadcast_sender.subscribe()
+    }
+
+    pub fn has_listeners(&self) -> bool {
+        self.0.broadcast_sender.receiver_count() != 0 || self.0.history.lock().capacity() != 0
+    }
+
+    pub fn history(&self) -> MutexGuard<'_, MessageHistory> {
+        self.0.history.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_message(sequence_counter: u64) -> Message {
+        Message {
+            sequence_counter,
+            ..Message::for_test()
+        }
+    }
+
+    fn collect_sequence_counters<'a>(iter: impl Iterator<Item = &'a Message>) -> Vec<(u64, u64)> {
+        iter.map(|message| (message.event_id, message.sequence_counter))
+            .collect()
+    }
+
+    #[test]
+    fn test_message_history_evicts_oldest_messages() {
+        let mut history = MessageHistory::new(2);
+
+        for sequence_counter in 10..13 {
+            history.push(&mut create_message(sequence_counter));
+        }
+
+        assert_eq!(
+            collect_sequence_counters(history.iter()),
+            vec![(1, 11), (2, 12)]
+        );
+    }
+
+    #[test]
+    fn test_message_history_iter_after() {
+        let mut history = MessageHistory::new(4);
+        for sequence_counter in 0..4 {
+            history.push(&mut create_message(sequence_counter));
+        }
+
+        assert_eq!(
+            collect_sequence_counters(history.iter_after(None)),
+            vec![(0, 0), (1, 1), (2, 2), (3, 3)]
+        );
+        assert_eq!(
+            collect_sequence_counters(history.iter_after(Some(1))),
+            vec![(2, 2), (3, 3)]
+        );
+        assert_eq!(
+            collect_sequence_counters(history.iter_after(Some(3))),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_message_history_messages_from() {
+        let mut history = MessageHistory::new(2);
+        for sequence_counter in 0..4 {
+            history.push(&mut create_message(sequence_counter));
+        }
+
+        let (messages, skipped) = history.messages_from(0);
+        assert_eq!(
+            collect_sequence_counters(messages.iter()),
+            vec![(2, 2), (3, 3)]
+        );
+        assert_eq!(skipped, 2);
+
+        let (messages, skipped) = history.messages_from(3);
+        assert_eq!(collect_sequence_counters(messages.iter()), vec![(3, 3)]);
+        assert_eq!(skipped, 0);
+
+        let (messages, skipped) = history.messages_from(history.next_event_id());
+        assert_eq!(collect_sequence_counters(messages.iter()), vec![]);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn test_message_history_disabled() {
+        let mut history = MessageHistory::new(0);
+
+        let mut message = create_message(0);
+        history.push(&mut message);
+        assert_eq!(message.event_id, 0);
+
+        let mut message = create_message(1);
+        history.push(&mut message);
+        assert_eq!(message.event_id, 1);
+
+        assert_eq!(collect_sequence_counters(history.iter()), vec![]);
+        let (messages, skipped) = history.messages_from(0);
+        assert!(messages.is_empty());
+        assert_eq!(skipped, 2);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use async_graphql::{dynamic::FieldValue, Value};
+use async_graphql_parser::types::{BaseType, Type};
+
+use crate::utils::mix_bits;
+
+const FIRST_NAMES: &[&str] = &[
+    "Alice", "Bob", "Carol", "Dave", "Erin", "Frank", "Grace", "Heidi", "Ivan", "Judy",
+];
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Johnson", "Brown", "Taylor", "Miller", "Wilson", "Moore", "Clark", "Lewis", "Young",
+];
+const WORDS: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "tempor",
+    "incididunt",
+    "labore",
+];
+
+pub struct MockObject {
+    pub path: String,
+    pub data: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum MockTypeKind {
+    #[default]
+    Scalar,
+    Enum(Vec<String>),
+    Object,
+    Abstract(Vec<String>),
+}
+
+#[derive(Debug, Default)]
+pub struct MockDataGenerator {
+    seed: u64,
+    type_kinds: HashMap<String, MockTypeKind>,
+    overrides: HashMap<String, serde_json::Value>,
+}
+
+impl MockDataGenerator {
+    pub fn new(
+        seed: u64,
+        type_kinds: HashMap<String, MockTypeKind>,
+        overrides: HashMap<String, serde_json::Value>,
+    ) -> Self {
+        Self {
+            seed,
+            type_kinds,
+            overrides,
+        }
+    }
+
+    // the generated values only depend on the seed and the path of the value, so the same
+    // operation always produces the same response
+    fn random(&self, path: &str) -> u64 {
+        let mut hash = 0xcbf29ce484222325 ^ self.seed;
+        for byte in path.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+
+        mix_bits(hash)
+    }
+
+    fn pick<'a>(&self, path: &str, items: &'a [&'a str]) -> &'a str {
+        items[(self.random(path) % items.len() as u64) as usize]
+    }
+
+    fn choose<'a, T>(&self, path: &str, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get((self.random(path) % items.len() as u64) as usize)
+        }
+    }
+
+    pub fn generate(
+        &self,
+        ty: &Type,
+        field_name: &str,
+        path: &str,
+        data: Option<serde_json::Value>,
+    ) -> Option<FieldValue<'static>> {
+        if ty.nullable && data.as_ref().is_some_and(|data| data.is_null()) {
+            return None;
+        }
+
+        match &ty.base {
+            BaseType::List(item_type) => {
+                let items = match data {
+                    Some(serde_json::Value::Array(items)) => {
+                        items.into_iter().map(Some).collect::<Vec<_>>()
+                    }
+                    _ => vec![None; 1 + (self.random(path) % 3) as usize],
+                };
+
+                Some(FieldValue::list(items.into_iter().enumerate().map(
+                    |(index, data)| {
+                        self.generate(item_type, field_name, &format!("{path}[{index}]"), data)
+                            .unwrap_or(FieldValue::NULL)
+                    },
+                )))
+            }
+            BaseType::Named(type_name) => {
+                Some(self.generate_named(type_name, field_name, path, data))
+            }
+        }
+    }
+
+    fn generate_named(
+        &self,
+        type_name: &str,
+        field_name: &str,
+        path: &str,
+        data: Option<serde_json::Value>,
+    ) -> FieldValue<'static> {
+        match self.type_kinds.get(type_name).cloned().unwrap_or_default() {
+            MockTypeKind::Scalar | MockTypeKind::Enum(_) if data.is_some() => {
+                FieldValue::value(Value::from_json(data.unwrap_or_default()).unwrap_or_default())
+            }
+            MockTypeKind::Scalar => match self.overrides.get(type_name) {
+                Some(value) => {
+                    FieldValue::value(Value::from_json(value.clone()).unwrap_or_default())
+                }
+                None => FieldValue::value(self.generate_scalar(type_name, field_name, path)),
+            },
+            MockTypeKind::Enum(values) => match self.overrides.get(type_name) {
+                Some(value) => {
+                    FieldValue::value(Value::from_json(value.clone()).unwrap_or_default())
+                }
+                None => match self.choose(path, &values) {
+                    Some(value) => FieldValue::value(Value::Enum(async_graphql::Name::new(value))),
+                    None => FieldValue::NULL,
+                },
+            },
+            MockTypeKind::Object => self.generate_object(type_name, path, data),
+            MockTypeKind::Abstract(possible_types) => {
+                let type_name = data
+                    .as_ref()
+                    .and_then(|data| data.get("__typename"))
+                    .and_then(|type_name| type_name.as_str())
+                    .map(|type_name| type_name.to_string())
+                    .or_else(|| self.choose(path, &possible_types).cloned());
+
+                match type_name {
+                    Some(type_name) => self
+                        .generate_object(&type_name, path, data)
+                        .with_type(type_name),
+                    None => FieldValue::NULL,
+                }
+            }
+        }
+    }
+
+    fn generate_object(
+        &self,
+        type_name: &str,
+        path: &str,
+        data: Option<serde_json::Value>,
+    ) -> FieldValue<'static> {
+        let mut object_data = match self.overrides.get(type_name) {
+            Some(serde_json::Value::Object(data)) => data.clone(),
+            _ => serde_json::Map::new(),
+        };
+        if let Some(serde_json::Value::Object(data)) = data {
+            object_data.extend(data);
+        }
+
+        FieldValue::owned_any(MockObject {
+            path: path.to_string(),
+            data: object_data,
+        })
+    }
+
+    fn generate_scalar(&self, type_name: &str, field_name: &str, path: &str) -> Value {
+        let random = self.random(path);
+        let field_name = field_name.to_lowercase();
+
+        match type_name {
+            "Int" => Value::from((random % 1000) as i32),
+            "Float" => Value::from((random % 100000) as f64 / 100.0),
+            "Boolean" => Value::from(random & 1 == 0),
+            "ID" => Value::from(format!("{:016x}", random)),
+            _ if field_name.contains("email") => Value::from(format!(
+                "{}.{}@example.com",
+                self.pick(&format!("{path}#first"), FIRST_NAMES)
+                    .to_lowercase(),
+                self.pick(&format!("{path}#last"), LAST_NAMES)
+                    .to_lowercase(),
+            )),
+            _ if field_name.contains("url") || field_name.contains("link") => {
+                Value::from(format!("https://example.com/{}", random % 10000))
+            }
+            _ if type_name.contains("Date")
+                || type_name.contains("Time")
+                || field_name.ends_with("at")
+                || field_name.contains("date")
+                || field_name.contains("time") =>
+            {
+                let timestamp = SystemTime::UNIX_EPOCH
+                    + Duration::from_secs(1_600_000_000 + random % 100_000_000);
+                Value::from(humantime::format_rfc3339_seconds(timestamp).to_string())
+            }
+            _ if field_name.contains("name") => Value::from(format!(
+                "{} {}",
+                self.pick(&format!("{path}#first"), FIRST_NAMES),
+                self.pick(&format!("{path}#last"), LAST_NAMES),
+            )),
+            _ => {
+                let word_count = 2 + random % 3;
+                let words = (0..word_count)
+                    .map(|index| self.pick(&format!("{path}#{index}"), WORDS))
+                    .collect::<Vec<_>>();
+                Value::from(words.join(" "))
+            }
+        }
+    }
+}
@@ -0,0 +1,659 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_graphql::dynamic::{
+    Enum, Field, FieldFuture, InputObject, InputValue, Interface, InterfaceField, Object,
+    ResolverContext, Scalar, Schema, Subscription, SubscriptionField, SubscriptionFieldFuture,
+    TypeRef, Union,
+};
+use async_graphql_parser::{
+    parse_schema,
+    types::{
+        BaseType, FieldDefinition, InputValueDefinition, Type, TypeDefinition, TypeKind,
+        TypeSystemDefinition,
+    },
+};
+
+use crate::{
+    error::{
+        CannotBuildMockSchemaError, CannotParseMockOverrideFileError, CannotParseSdlError,
+        CannotReadMockOverrideFileError, CannotReadSdlFileError, LoadMockSchemaError,
+    },
+    log_location,
+};
+
+use super::mock_data::{MockDataGenerator, MockObject, MockTypeKind};
+
+const BUILT_IN_SCALARS: &[&str] = &["Int", "Float", "String", "Boolean", "ID"];
+
+#[derive(Debug, Clone, Default)]
+pub struct MockSchemaConfig {
+    pub seed: u64,
+    pub overrides: HashMap<String, serde_json::Value>,
+    pub subscription_interval: Duration,
+}
+
+struct SchemaRoots {
+    query: String,
+    mutation: Option<String>,
+    subscription: Option<String>,
+}
+
+fn to_type_ref(ty: &Type) -> TypeRef {
+    let type_ref = match &ty.base {
+        BaseType::Named(type_name) => TypeRef::named(type_name.as_str()),
+        BaseType::List(item_type) => TypeRef::List(Box::new(to_type_ref(item_type))),
+    };
+
+    if ty.nullable {
+        type_ref
+    } else {
+        TypeRef::NonNull(Box::new(type_ref))
+    }
+}
+
+fn to_input_value(input_value_definition: &InputValueDefinition) -> InputValue {
+    let input_value = InputValue::new(
+        input_value_definition.name.node.as_str(),
+        to_type_ref(&input_value_definition.ty.node),
+    );
+
+    match &input_value_definition.default_value {
+        Some(default_value) => input_value.default_value(default_value.node.clone()),
+        None => input_value,
+    }
+}
+
+fn extend_type_definition(type_definition: &mut TypeDefinition, extension: TypeDefinition) {
+    match (&mut type_definition.kind, extension.kind) {
+        (TypeKind::Object(object), TypeKind::Object(extension)) => {
+            object.implements.extend(extension.implements);
+            object.fields.extend(extension.fields);
+        }
+        (TypeKind::Interface(interface), TypeKind::Interface(extension)) => {
+            interface.implements.extend(extension.implements);
+            interface.fields.extend(extension.fields);
+        }
+        (TypeKind::Union(union), TypeKind::Union(extension)) => {
+            union.members.extend(extension.members);
+        }
+        (TypeKind::Enum(enum_type), TypeKind::Enum(extension)) => {
+            enum_type.values.extend(extension.values);
+        }
+        (TypeKind::InputObject(input_object), TypeKind::InputObject(extension)) => {
+            input_object.fields.extend(extension.fields);
+        }
+        (TypeKind::Scalar, TypeKind::Scalar) => (),
+        _ => log::warn!(
+            "{}, extension of type = '{}' has a different kind, it is ignored",
+            log_location!(),
+            type_definition.name.node
+        ),
+    }
+}
+
+fn collect_type_definitions(
+    definitions: Vec<TypeSystemDefinition>,
+) -> (Vec<TypeDefinition>, SchemaRoots) {
+    let mut type_definitions: Vec<TypeDefinition> = Vec::new();
+    let mut type_indices = HashMap::<String, usize>::new();
+    let mut schema_roots = SchemaRoots {
+        query: "Query".to_string(),
+        mutation: None,
+        subscription: None,
+    };
+    let mut has_schema_definition = false;
+
+    for definition in definitions {
+        match definition {
+            TypeSystemDefinition::Schema(schema_definition) => {
+                has_schema_definition = true;
+                let schema_definition = schema_definition.node;
+                if let Some(query) = schema_definition.query {
+                    schema_roots.query = query.node.to_string();
+                }
+                if let Some(mutation) = schema_definition.mutation {
+                    schema_roots.mutation = Some(mutation.node.to_string());
+                }
+                if let Some(subscription) = schema_definition.subscription {
+                    schema_roots.subscription = Some(subscription.node.to_string());
+                }
+            }
+            TypeSystemDefinition::Type(type_definition) => {
+                let type_definition = type_definition.node;
+                match type_indices.get(type_definition.name.node.as_str()) {
+                    Some(index) => {
+                        extend_type_definition(&mut type_definitions[*index], type_definition)
+                    }
+                    None => {
+                        type_indices.insert(
+                            type_definition.name.node.to_string(),
+                            type_definitions.len(),
+                        );
+                        type_definitions.push(type_definition);
+                    }
+                }
+            }
+            TypeSystemDefinition::Directive(_directive_definition) => (),
+        }
+    }
+
+    // without a schema definition the root types are found by their conventional names
+    if !has_schema_definition {
+        schema_roots.mutation = type_indices
+            .contains_key("Mutation")
+            .then(|| "Mutation".to_string());
+        schema_roots.subscription = type_indices
+            .contains_key("Subscription")
+            .then(|| "Subscription".to_string());
+    }
+
+    (type_definitions, schema_roots)
+}
+
+fn collect_type_kinds(type_definitions: &[TypeDefinition]) -> HashMap<String, MockTypeKind> {
+    let mut type_kinds = HashMap::new();
+
+    for type_definition in type_definitions {
+        let type_kind = match &type_definition.kind {
+            TypeKind::Scalar | TypeKind::InputObject(_) => MockTypeKind::Scalar,
+            TypeKind::Enum(enum_type) => MockTypeKind::Enum(
+                enum_type
+                    .values
+                    .iter()
+                    .map(|value| value.node.value.node.to_string())
+                    .collect(),
+            ),
+            TypeKind::Object(_) => MockTypeKind::Object,
+            TypeKind::Union(union) => MockTypeKind::Abstract(
+                union
+                    .members
+                    .iter()
+                    .map(|member| member.node.to_string())
+                    .collect(),
+            ),
+            TypeKind::Interface(_) => MockTypeKind::Abstract(
+                type_definitions
+                    .iter()
+                    .filter(|implementor| match &implementor.kind {
+                        TypeKind::Object(object) => object
+                            .implements
+                            .iter()
+                            .any(|name| name.node == type_definition.name.node),
+                        _ => false,
+                    })
+                    .map(|implementor| implementor.name.node.to_string())
+                    .collect(),
+            ),
+        };
+
+        type_kinds.insert(type_definition.name.node.to_string(), type_kind);
+    }
+
+    type_kinds
+}
+
+fn field_path_and_data(
+    ctx: &ResolverContext,
+    field_name: &str,
+) -> (String, Option<serde_json::Value>) {
+    let parent = ctx.parent_value.downcast_ref::<MockObject>();
+
+    let mut path = match parent {
+        Some(parent) => format!("{}.{field_name}", parent.path),
+        None => field_name.to_string(),
+    };
+
+    if !ctx.args.is_empty() {
+        let arguments = ctx
+            .args
+            .as_index_map()
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}"))
+            .collect::<Vec<_>>();
+        path.push_str(&format!("({})", arguments.join(",")));
+    }
+
+    let data = parent.and_then(|parent| parent.data.get(field_name).cloned());
+
+    (path, data)
+}
+
+fn create_field(generator: Arc<MockDataGenerator>, field_definition: &FieldDefinition) -> Field {
+    let field_name = field_definition.name.node.to_string();
+    let ty = field_definition.ty.node.clone();
+
+    let field = Field::new(
+        field_name.clone(),
+        to_type_ref(&field_definition.ty.node),
+        move |ctx| {
+            let (path, data) = field_path_and_data(&ctx, &field_name);
+            FieldFuture::Value(generator.generate(&ty, &field_name, &path, data))
+        },
+    );
+
+    field_definition
+        .arguments
+        .iter()
+        .fold(field, |field, argument| {
+            field.argument(to_input_value(&argument.node))
+        })
+}
+
+fn create_subscription_field(
+    generator: Arc<MockDataGenerator>,
+    field_definition: &FieldDefinition,
+    subscription_interval: Duration,
+) -> SubscriptionField {
+    let field_name = field_definition.name.node.to_string();
+    let ty = field_definition.ty.node.clone();
+
+    let field = SubscriptionField::new(
+        field_name.clone(),
+        to_type_ref(&field_definition.ty.node),
+        move |ctx| {
+            let (path, _data) = field_path_and_data(&ctx, &field_name);
+            let generator = generator.clone();
+            let field_name = field_name.clone();
+            let ty = ty.clone();
+
+            SubscriptionFieldFuture::new(async move {
+                Ok(async_stream::stream! {
+                    for event_index in 0u64.. {
+                        tokio::time::sleep(subscription_interval).await;
+
+                        let value = generator.generate(
+                            &ty,
+                            &field_name,
+                            &format!("{path}#{event_index}"),
+                            None,
+                        );
+                        yield Ok(value.unwrap_or(async_graphql::dynamic::FieldValue::NULL));
+                    }
+                })
+            })
+        },
+    );
+
+    field_definition
+        .arguments
+        .iter()
+        .fold(field, |field, argument| {
+            field.argument(to_input_value(&argument.node))
+        })
+}
+
+pub fn create_mock_schema(
+    sdl: &str,
+    config: MockSchemaConfig,
+) -> Result<Schema, LoadMockSchemaError> {
+    let service_document = parse_schema(sdl).map_err(|e| CannotParseSdlError { source: e })?;
+    let (type_definitions, schema_roots) = collect_type_definitions(service_document.definitions);
+
+    let generator = Arc::new(MockDataGenerator::new(
+        config.seed,
+        collect_type_kinds(&type_definitions),
+        config.overrides,
+    ));
+
+    let mut schema_builder = Schema::build(
+        &schema_roots.query,
+        schema_roots.mutation.as_deref(),
+        schema_roots.subscription.as_deref(),
+    );
+
+    for type_definition in type_definitions {
+        let type_name = type_definition.name.node.as_str();
+
+        schema_builder = match &type_definition.kind {
+            TypeKind::Scalar => {
+                if BUILT_IN_SCALARS.contains(&type_name) {
+                    schema_builder
+                } else {
+                    schema_builder.register(Scalar::new(type_name))
+                }
+            }
+            TypeKind::Object(object_type)
+                if schema_roots.subscription.as_deref() == Some(type_name) =>
+            {
+                schema_builder.register(object_type.fields.iter().fold(
+                    Subscription::new(type_name),
+                    |subscription, field_definition| {
+                        subscription.field(create_subscription_field(
+                            generator.clone(),
+                            &field_definition.node,
+                            config.subscription_interval,
+                        ))
+                    },
+                ))
+            }
+            TypeKind::Object(object_type) => {
+                let object = object_type
+                    .implements
+                    .iter()
+                    .fold(Object::new(type_name), |object, interface| {
+                        object.implement(interface.node.as_str())
+                    });
+
+                schema_builder.register(object_type.fields.iter().fold(
+                    object,
+                    |object, field_definition| {
+                        object.field(create_field(generator.clone(), &field_definition.node))
+                    },
+                ))
+            }
+            TypeKind::Interface(interface_type) => {
+                let interface = interface_type
+                    .implements
+                    .iter()
+                    .fold(Interface::new(type_name), |interface, implemented| {
+                        interface.implement(implemented.node.as_str())
+                    });
+
+                schema_builder.register(interface_type.fields.iter().fold(
+                    interface,
+                    |interface, field_definition| {
+                        let field_definition = &field_definition.node;
+                        let field = InterfaceField::new(
+                            field_definition.name.node.as_str(),
+                            to_type_ref(&field_definition.ty.node),
+                        );
+
+                        interface.field(
+                            field_definition
+                                .arguments
+                                .iter()
+                                .fold(field, |field, argument| {
+                                    field.argument(to_input_value(&argument.node))
+                                }),
+                        )
+                    },
+                ))
+            }
+            TypeKind::Union(union_type) => schema_builder.register(
+                union_type
+                    .members
+                    .iter()
+                    .fold(Union::new(type_name), |union, member| {
+                        union.possible_type(member.node.as_str())
+                    }),
+            ),
+            TypeKind::Enum(enum_type) => schema_builder.register(
+                Enum::new(type_name).items(
+                    enum_type
+                        .values
+                        .iter()
+                        .map(|value| value.node.value.node.as_str()),
+                ),
+            ),
+            TypeKind::InputObject(input_object_type) => schema_builder.register(
+                input_object_type
+                    .fields
+                    .iter()
+                    .fold(InputObject::new(type_name), |input_object, field| {
+                        input_object.field(to_input_value(&field.node))
+                    }),
+            ),
+        };
+    }
+
+    Ok(schema_builder
+        .finish()
+        .map_err(|e| CannotBuildMockSchemaError { source: e })?)
+}
+
+fn collect_override_files(path: &Path) -> Result<Vec<PathBuf>, CannotReadMockOverrideFileError> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = std::fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| CannotReadMockOverrideFileError {
+            path: path.to_path_buf(),
+            source: e,
+        })?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+pub fn load_mock_overrides(
+    paths: &[PathBuf],
+) -> Result<HashMap<String, serde_json::Value>, LoadMockSchemaError> {
+    let mut overrides = HashMap::new();
+
+    for path in paths {
+        for path in collect_override_files(path)? {
+            let content =
+                std::fs::read_to_string(&path).map_err(|e| CannotReadMockOverrideFileError {
+                    path: path.clone(),
+                    source: e,
+                })?;
+            let value =
+                serde_json::from_str(&content).map_err(|e| CannotParseMockOverrideFileError {
+                    path: path.clone(),
+                    source: e,
+                })?;
+
+            let type_name = path
+                .file_stem()
+                .map(|file_stem| file_stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            overrides.insert(type_name, value);
+        }
+    }
+
+    Ok(overrides)
+}
+
+pub fn load_mock_schema(
+    sdl_path: &Path,
+    config: MockSchemaConfig,
+) -> Result<Schema, LoadMockSchemaError> {
+    let sdl = std::fs::read_to_string(sdl_path).map_err(|e| CannotReadSdlFileError {
+        path: sdl_path.to_path_buf(),
+        source: e,
+    })?;
+
+    create_mock_schema(&sdl, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    const SDL: &str = r#"
+        scalar DateTime
+
+        enum Role { ADMIN USER }
+
+        interface Node { id: ID! }
+
+        type User implements Node {
+            id: ID!
+            name: String!
+            email: String
+            role: Role!
+            createdAt: DateTime!
+            friends(first: Int = 2): [User!]!
+        }
+
+        type Post implements Node {
+            id: ID!
+            title: String!
+            author: User!
+        }
+
+        union SearchResult = User | Post
+
+        input PostFilter { title: String }
+
+        type Query {
+            user(id: ID!): User
+            node(id: ID!): Node
+            search(filter: PostFilter): [SearchResult!]!
+        }
+
+        extend type Query {
+            version: Int!
+        }
+
+        type Subscription {
+            userCreated: User!
+        }
+    "#;
+
+    const QUERY: &str = r#"
+        {
+            user(id: "1") { id name email role createdAt friends { id name } }
+            node(id: "2") { id __typename }
+            search(filter: { title: "foo" }) {
+                __typename
+                ... on User { name }
+                ... on Post { title author { name } }
+            }
+            version
+        }
+    "#;
+
+    async fn execute(config: MockSchemaConfig, query: &str) -> serde_json::Value {
+        let response = create_mock_schema(SDL, config)
+            .unwrap()
+            .execute(query)
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        response.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mock_schema_is_deterministic() {
+        let config = MockSchemaConfig {
+            seed: 1,
+            ..Default::default()
+        };
+
+        let data = execute(config.clone(), QUERY).await;
+        assert_eq!(data, execute(config, QUERY).await);
+
+        assert!(data["user"]["name"].is_string());
+        assert!(data["user"]["friends"].is_array());
+        assert!(["ADMIN", "USER"].contains(&data["user"]["role"].as_str().unwrap()));
+        assert!(humantime::parse_rfc3339(data["user"]["createdAt"].as_str().unwrap()).is_ok());
+        assert!(["User", "Post"].contains(&data["node"]["__typename"].as_str().unwrap()));
+        assert!(data["version"].is_i64());
+
+        let other_seed_data = execute(
+            MockSchemaConfig {
+                seed: 2,
+                ..Default::default()
+            },
+            QUERY,
+        )
+        .await;
+        assert_ne!(data, other_seed_data);
+    }
+
+    #[tokio::test]
+    async fn test_mock_schema_overrides() {
+        let config = MockSchemaConfig {
+            overrides: HashMap::from([
+                (
+                    "User".to_string(),
+                    serde_json::json!({ "name": "Alice", "email": null, "friends": [{ "name": "Bob" }] }),
+                ),
+                (
+                    "DateTime".to_string(),
+                    serde_json::json!("2024-01-01T00:00:00Z"),
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let data = execute(
+            config,
+            r#"{ user(id: "1") { name email createdAt friends { name } } }"#,
+        )
+        .await;
+
+        assert_eq!(
+            data,
+            serde_json::json!({
+                "user": {
+                    "name": "Alice",
+                    "email": null,
+                    "createdAt": "2024-01-01T00:00:00Z",
+                    "friends": [{ "name": "Bob" }],
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_schema_rejects_invalid_operations() {
+        let response = create_mock_schema(SDL, MockSchemaConfig::default())
+            .unwrap()
+            .execute("{ user { unknownField } }")
+            .await;
+
+        assert!(!response.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_schema_subscription() {
+        let schema = create_mock_schema(SDL, MockSchemaConfig::default()).unwrap();
+
+        let responses = schema
+            .execute_stream("subscription { userCreated { id name } }")
+            .take(2)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(responses.len(), 2);
+        assert!(responses.iter().all(|response| response.errors.is_empty()));
+        assert_ne!(
+            responses[0].data.clone().into_json().unwrap(),
+            responses[1].data.clone().into_json().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_schema_interface_without_implementations() {
+        let response = create_mock_schema(
+            r#"
+                interface Node { id: ID! }
+
+                type Query {
+                    node: Node
+                    version: Int!
+                }
+            "#,
+            MockSchemaConfig::default(),
+        )
+        .unwrap()
+        .execute("{ node { id } version }")
+        .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert!(data["node"].is_null());
+        assert!(data["version"].is_i64());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod mock_data;
+pub mod mock_schema;
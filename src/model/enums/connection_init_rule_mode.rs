@@ -0,0 +1,8 @@
+use async_graphql::Enum;
+
+#[derive(Debug, Enum, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConnectionInitRuleMode {
+    Merge,
+    Override,
+}
@@ -0,0 +1,26 @@
+use async_graphql::Enum;
+
+use super::message_direction::MessageDirection;
+
+#[derive(Debug, Enum, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MessageOrigin {
+    Client,
+    Server,
+    Proxy,
+    Stub,
+    Cassette,
+    Fault,
+    Breakpoint,
+    Replay,
+    Injected,
+}
+
+impl MessageOrigin {
+    pub fn from_message_direction(message_direction: MessageDirection) -> Self {
+        match message_direction {
+            MessageDirection::Request => Self::Client,
+            MessageDirection::Response => Self::Server,
+        }
+    }
+}
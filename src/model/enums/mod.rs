@@ -1,4 +1,8 @@
+pub mod connection_init_rule_mode;
 pub mod connection_type;
 pub mod filter_type;
+pub mod http_method;
 pub mod message_direction;
+pub mod message_origin;
+pub mod operation_type;
 pub mod payload_type;
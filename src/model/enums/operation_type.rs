@@ -0,0 +1,21 @@
+use async_graphql::Enum;
+use async_graphql_parser::types::OperationType;
+
+#[derive(Debug, Enum, Copy, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[graphql(name = "OperationType")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GraphQLOperationType {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+impl From<OperationType> for GraphQLOperationType {
+    fn from(operation_type: OperationType) -> Self {
+        match operation_type {
+            OperationType::Query => Self::Query,
+            OperationType::Mutation => Self::Mutation,
+            OperationType::Subscription => Self::Subscription,
+        }
+    }
+}
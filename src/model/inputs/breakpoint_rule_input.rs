@@ -0,0 +1,14 @@
+use async_graphql::InputObject;
+
+use crate::model::enums::{
+    connection_type::ConnectionType, message_direction::MessageDirection,
+    operation_type::GraphQLOperationType,
+};
+
+#[derive(Debug, Clone, InputObject)]
+pub struct BreakpointRuleInput {
+    pub connection_type: Option<ConnectionType>,
+    pub message_direction: Option<MessageDirection>,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+}
@@ -0,0 +1,17 @@
+use async_graphql::InputObject;
+
+use crate::model::enums::{connection_type::ConnectionType, operation_type::GraphQLOperationType};
+
+#[derive(Debug, Clone, InputObject)]
+pub struct FaultRuleInput {
+    pub connection_type: Option<ConnectionType>,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+    #[graphql(default = 100.0)]
+    pub percentage: f64,
+    pub latency_ms: Option<u64>,
+    pub error_message: Option<String>,
+    pub status: Option<u16>,
+    pub truncate_body_bytes: Option<u64>,
+    pub close_after_messages: Option<u64>,
+}
@@ -0,0 +1,11 @@
+use async_graphql::InputObject;
+
+use crate::model::scalars::{
+    header_name_scalar::HeaderNameScalar, header_value_scalar::HeaderValueScalar,
+};
+
+#[derive(Debug, Clone, InputObject)]
+pub struct HeaderInput {
+    pub name: HeaderNameScalar,
+    pub value: HeaderValueScalar,
+}
@@ -15,9 +15,21 @@ pub struct MessageFilter {
     pub connection_type: Option<ConnectionType>,
     pub message_direction: Option<MessageDirection>,
     pub payload_type: Option<PayloadType>,
+    pub min_latency_ms: Option<f64>,
 }
 
 impl MessageFilter {
+    pub fn is_message_allowed_by_filters(message_filters: &[Self], message: &Message) -> bool {
+        let mut is_message_allowed = true;
+        for filter in message_filters.iter() {
+            if let Some(allowed) = filter.is_message_allowed(message) {
+                is_message_allowed = allowed;
+            }
+        }
+
+        is_message_allowed
+    }
+
     pub fn is_message_allowed(&self, message: &Message) -> Option<bool> {
         let is_matching = self.is_message_matching(message);
 
@@ -61,6 +73,13 @@ impl MessageFilter {
             }
         }
 
+        if let Some(min_latency_ms) = self.min_latency_ms {
+            match message.latency {
+                Some(latency) if latency.as_secs_f64() * 1000.0 >= min_latency_ms => (),
+                _ => return false,
+            }
+        }
+
         true
     }
 }
@@ -152,7 +171,7 @@ impl MessageFilterCliParser {
     }
 
     fn create_error_message(&self) -> String {
-        let mut message = "Invalid message filter format. Expected <filter_type>:<connection-type>,<message-direction>,<payload-type>".to_string();
+        let mut message = "Invalid message filter format. Expected <filter_type>:<connection-type>,<message-direction>,<payload-type>[,<min-latency>]".to_string();
 
         message += "; ";
         message += &self.create_filter_type_variants_message();
@@ -226,6 +245,17 @@ impl MessageFilterCliParser {
                 }
             };
 
+        let min_latency_ms = match collection.next() {
+            Some(min_latency_str) if !min_latency_str.eq_ignore_ascii_case("any") => Some(
+                min_latency_str
+                    .parse::<humantime::Duration>()
+                    .map_err(|e| format!("{e}, <min-latency> has to be a duration (e.g., 100ms)"))?
+                    .as_secs_f64()
+                    * 1000.0,
+            ),
+            _ => None,
+        };
+
         if collection.next().is_some() {
             return Err(self.create_error_message());
         }
@@ -235,6 +265,7 @@ impl MessageFilterCliParser {
             connection_type,
             message_direction,
             payload_type,
+            min_latency_ms,
         })
     }
 }
@@ -266,7 +297,10 @@ mod tests {
     fn test_message_filter_cli_parser() {
         let parser = MessageFilterCliParser;
 
-        assert!(matches!(parser.try_parse("allow:any,any,any,any"), Err(_)));
+        assert!(matches!(
+            parser.try_parse("allow:any,any,any,any,any"),
+            Err(_)
+        ));
 
         // FilterType
         {
@@ -277,6 +311,7 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: None,
+                    min_latency_ms: None,
                 })
             ));
 
@@ -287,6 +322,7 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: None,
+                    min_latency_ms: None,
                 })
             ));
         }
@@ -300,6 +336,7 @@ mod tests {
                     connection_type: Some(ConnectionType::Http),
                     message_direction: None,
                     payload_type: None,
+                    min_latency_ms: None,
                 })
             ));
 
@@ -310,6 +347,7 @@ mod tests {
                     connection_type: Some(ConnectionType::Ws),
                     message_direction: None,
                     payload_type: None,
+                    min_latency_ms: None,
                 })
             ));
         }
@@ -323,6 +361,7 @@ mod tests {
                     connection_type: None,
                     message_direction: Some(MessageDirection::Request),
                     payload_type: None,
+                    min_latency_ms: None,
                 })
             ));
 
@@ -333,6 +372,7 @@ mod tests {
                     connection_type: None,
                     message_direction: Some(MessageDirection::Response),
                     payload_type: None,
+                    min_latency_ms: None,
                 })
             ));
         }
@@ -346,6 +386,7 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: Some(PayloadType::Request),
+                    min_latency_ms: None,
                 })
             ));
             assert!(matches!(
@@ -355,6 +396,7 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: Some(PayloadType::OnlyData),
+                    min_latency_ms: None,
                 })
             ));
             assert!(matches!(
@@ -364,6 +406,7 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: Some(PayloadType::OnlyError),
+                    min_latency_ms: None,
                 })
             ));
             assert!(matches!(
@@ -373,6 +416,7 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: Some(PayloadType::PartialDataAndError),
+                    min_latency_ms: None,
                 })
             ));
             assert!(matches!(
@@ -382,8 +426,36 @@ mod tests {
                     connection_type: None,
                     message_direction: None,
                     payload_type: Some(PayloadType::NonGraphQl),
+                    min_latency_ms: None,
                 })
             ));
         }
+
+        // MinLatency
+        {
+            assert!(matches!(
+                parser.try_parse("allow:any,any,any,any"),
+                Ok(MessageFilter {
+                    filter_type: FilterType::Allow,
+                    connection_type: None,
+                    message_direction: None,
+                    payload_type: None,
+                    min_latency_ms: None,
+                })
+            ));
+
+            assert!(matches!(
+                parser.try_parse("allow:any,response,any,1s"),
+                Ok(MessageFilter {
+                    filter_type: FilterType::Allow,
+                    connection_type: None,
+                    message_direction: Some(MessageDirection::Response),
+                    payload_type: None,
+                    min_latency_ms: Some(1000.0),
+                })
+            ));
+
+            assert!(matches!(parser.try_parse("allow:any,any,any,foo"), Err(_)));
+        }
     }
 }
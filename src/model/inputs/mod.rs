@@ -1 +1,6 @@
+pub mod breakpoint_rule_input;
+pub mod fault_rule_input;
+pub mod header_input;
 pub mod message_filter;
+pub mod replay_overrides_input;
+pub mod upstream_profile_input;
@@ -0,0 +1,11 @@
+use async_graphql::InputObject;
+
+use super::header_input::HeaderInput;
+
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct ReplayOverridesInput {
+    pub variables: Option<serde_json::Value>,
+    pub operation_name: Option<String>,
+    #[graphql(default)]
+    pub headers: Vec<HeaderInput>,
+}
@@ -0,0 +1,16 @@
+use async_graphql::InputObject;
+
+use super::header_input::HeaderInput;
+
+#[derive(Debug, Clone, InputObject)]
+pub struct UpstreamProfileInput {
+    pub name: String,
+    #[graphql(name = "graphQlEndpoint")]
+    pub graphql_endpoint: String,
+    #[graphql(name = "graphQlWsEndpoint")]
+    pub graphql_ws_endpoint: String,
+    #[graphql(default)]
+    pub request_headers: Vec<HeaderInput>,
+    #[graphql(default)]
+    pub accept_invalid_certificates: bool,
+}
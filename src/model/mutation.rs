@@ -1,12 +1,47 @@
-use async_graphql::Object;
+use std::time::Duration;
 
-use crate::admin_state::AdminState;
+use async_graphql::{Context, Object, Variables};
+use axum::body::Body;
+use http::{HeaderMap, StatusCode};
+
+use crate::{
+    admin_state::AdminState,
+    app_state::AppState,
+    breakpoints::BreakpointResolution,
+    endpoints::graphql_proxy::{proxy_graphql_request, RequestEncoding},
+    error::{
+        InvalidCloseCodeError, InvalidFaultRulePercentageError, UnknownUpstreamProfileError,
+        UpstreamProfileAlreadyExistsError,
+    },
+    state_snapshot::StateSnapshot,
+};
 
 use super::{
-    scalars::{header_name_scalar::HeaderNameScalar, header_value_scalar::HeaderValueScalar},
-    types::graphql_endpoints::GraphQLEndpoints,
+    enums::{
+        connection_init_rule_mode::ConnectionInitRuleMode, connection_type::ConnectionType,
+        http_method::HttpMethod, message_direction::MessageDirection,
+    },
+    inputs::{
+        breakpoint_rule_input::BreakpointRuleInput, fault_rule_input::FaultRuleInput,
+        replay_overrides_input::ReplayOverridesInput, upstream_profile_input::UpstreamProfileInput,
+    },
+    scalars::{
+        event_id_scalar::EventIdScalar, header_name_scalar::HeaderNameScalar,
+        header_value_scalar::HeaderValueScalar,
+    },
+    types::{
+        breakpoint_rule::BreakpointRule, connection_init_rule::ConnectionInitRule,
+        fault_rule::FaultRule, graphql_endpoints::GraphQLEndpoints, headers::Headers,
+        operation_stub::OperationStub, paused_message::PausedMessage,
+        replayed_response::ReplayedResponse, upstream_profile::UpstreamProfile,
+    },
 };
 
+// a replayed response may be streamed endlessly (e.g., a multipart subscription), so it is read
+// only up to these limits
+const REPLAYED_RESPONSE_MAX_SIZE: usize = 16 * 1024 * 1024;
+const REPLAYED_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct Mutation {
     pub admin_state: AdminState,
 }
@@ -23,10 +58,10 @@ impl Mutation {
             graphql_ws_endpoint,
         };
 
-        std::mem::swap(
-            &mut endpoints,
-            &mut self.admin_state.server_graphql_endpoints_write(),
-        );
+        self.admin_state
+            .override_upstream_settings(|server_endpoints, _request_headers| {
+                std::mem::swap(&mut endpoints, server_endpoints);
+            });
 
         endpoints
     }
@@ -35,14 +70,63 @@ impl Mutation {
         self.admin_state.set_prohibit_mutation(prohibit_mutation)
     }
 
+    pub async fn create_profile(
+        &self,
+        profile: UpstreamProfileInput,
+    ) -> async_graphql::Result<UpstreamProfile> {
+        let upstream_profile = UpstreamProfile::new(profile);
+
+        let mut upstream_profiles = self.admin_state.upstream_profiles().write();
+        if upstream_profiles.contains_key(&upstream_profile.name) {
+            return Err(UpstreamProfileAlreadyExistsError {
+                name: upstream_profile.name,
+            }
+            .into());
+        }
+        upstream_profiles.insert(upstream_profile.name.clone(), upstream_profile.clone());
+
+        Ok(upstream_profile)
+    }
+
+    // the settings of a deleted active profile stay in effect until another one is activated
+    pub async fn delete_profile(&self, name: String) -> Option<UpstreamProfile> {
+        let upstream_profile = self.admin_state.upstream_profiles().write().remove(&name)?;
+
+        let mut active_upstream_profile = self.admin_state.active_upstream_profile().write();
+        if active_upstream_profile.as_ref() == Some(&name) {
+            *active_upstream_profile = None;
+        }
+
+        Some(upstream_profile)
+    }
+
+    pub async fn activate_profile(&self, name: String) -> async_graphql::Result<UpstreamProfile> {
+        Ok(self
+            .admin_state
+            .activate_upstream_profile(&name)
+            .ok_or(UnknownUpstreamProfileError { name })?)
+    }
+
+    pub async fn restore_state_snapshot(
+        &self,
+        snapshot: serde_json::Value,
+    ) -> async_graphql::Result<serde_json::Value> {
+        let snapshot = StateSnapshot::from_json(snapshot)?;
+        let replaced_snapshot = StateSnapshot::capture(&self.admin_state);
+        snapshot.restore(&self.admin_state)?;
+
+        Ok(serde_json::to_value(replaced_snapshot)?)
+    }
+
     pub async fn add_request_header(
         &self,
         name: HeaderNameScalar,
         value: HeaderValueScalar,
     ) -> bool {
-        let mut headers = self.admin_state.request_headers().write();
-
-        headers.insert(name.as_header_name().clone(), value.0);
+        self.admin_state
+            .override_upstream_settings(|_server_endpoints, headers| {
+                headers.insert(name.as_header_name().clone(), value.0);
+            });
 
         true
     }
@@ -52,19 +136,22 @@ impl Mutation {
         name: HeaderNameScalar,
         value: HeaderValueScalar,
     ) -> Option<HeaderValueScalar> {
-        let mut headers = self.admin_state.request_headers().write();
-
-        let old_header_value = headers.remove(name.as_header_name());
-        headers.insert(name.as_header_name().clone(), value.0);
+        let old_header_value =
+            self.admin_state
+                .override_upstream_settings(|_server_endpoints, headers| {
+                    let old_header_value = headers.remove(name.as_header_name());
+                    headers.insert(name.as_header_name().clone(), value.0);
+                    old_header_value
+                });
 
         old_header_value.map(|item| item.into())
     }
 
     pub async fn remove_request_header(&self, name: HeaderNameScalar) -> Option<HeaderValueScalar> {
         self.admin_state
-            .request_headers()
-            .write()
-            .remove(name.as_header_name())
+            .override_upstream_settings(|_server_endpoints, headers| {
+                headers.remove(name.as_header_name())
+            })
             .map(|item| item.into())
     }
 
@@ -103,4 +190,272 @@ impl Mutation {
             .remove(name.as_header_name())
             .map(|item| item.into())
     }
+
+    pub async fn set_operation_stub(
+        &self,
+        operation_name: String,
+        response: serde_json::Value,
+        #[graphql(default = 200)] status: u16,
+        #[graphql(default)] delay_ms: u64,
+    ) -> async_graphql::Result<Option<OperationStub>> {
+        StatusCode::from_u16(status)?;
+
+        let operation_stub = OperationStub {
+            operation_name: operation_name.clone(),
+            response,
+            status,
+            delay_ms,
+        };
+
+        Ok(self
+            .admin_state
+            .operation_stubs()
+            .write()
+            .insert(operation_name, operation_stub))
+    }
+
+    pub async fn remove_operation_stub(&self, operation_name: String) -> Option<OperationStub> {
+        self.admin_state
+            .operation_stubs()
+            .write()
+            .remove(&operation_name)
+    }
+
+    pub async fn add_fault_rule(&self, rule: FaultRuleInput) -> async_graphql::Result<FaultRule> {
+        if !(0.0..=100.0).contains(&rule.percentage) {
+            return Err(InvalidFaultRulePercentageError {
+                percentage: rule.percentage,
+            }
+            .into());
+        }
+        if let Some(status) = rule.status {
+            StatusCode::from_u16(status)?;
+        }
+
+        let fault_rule = FaultRule::new(rule);
+        self.admin_state
+            .fault_rules()
+            .write()
+            .push(fault_rule.clone());
+
+        Ok(fault_rule)
+    }
+
+    pub async fn remove_fault_rule(&self, id: String) -> Option<FaultRule> {
+        let mut fault_rules = self.admin_state.fault_rules().write();
+        let index = fault_rules.iter().position(|rule| rule.id == id)?;
+
+        Some(fault_rules.remove(index))
+    }
+
+    pub async fn clear_fault_rules(&self) -> Vec<FaultRule> {
+        std::mem::take(&mut *self.admin_state.fault_rules().write())
+    }
+
+    pub async fn set_connection_init_rule(
+        &self,
+        key: String,
+        value: serde_json::Value,
+        #[graphql(default_with = "ConnectionInitRuleMode::Override")] mode: ConnectionInitRuleMode,
+    ) -> Option<ConnectionInitRule> {
+        let connection_init_rule = ConnectionInitRule { key, value, mode };
+
+        let mut connection_init_rules = self.admin_state.connection_init_rules().write();
+        match connection_init_rules
+            .iter_mut()
+            .find(|rule| rule.key == connection_init_rule.key)
+        {
+            Some(rule) => Some(std::mem::replace(rule, connection_init_rule)),
+            None => {
+                connection_init_rules.push(connection_init_rule);
+                None
+            }
+        }
+    }
+
+    pub async fn remove_connection_init_rule(&self, key: String) -> Option<ConnectionInitRule> {
+        let mut connection_init_rules = self.admin_state.connection_init_rules().write();
+        let index = connection_init_rules
+            .iter()
+            .position(|rule| rule.key == key)?;
+
+        Some(connection_init_rules.remove(index))
+    }
+
+    pub async fn add_breakpoint_rule(&self, rule: BreakpointRuleInput) -> BreakpointRule {
+        let breakpoint_rule = BreakpointRule::new(rule);
+        self.admin_state
+            .breakpoints()
+            .rules()
+            .write()
+            .push(breakpoint_rule.clone());
+
+        breakpoint_rule
+    }
+
+    pub async fn remove_breakpoint_rule(&self, id: String) -> Option<BreakpointRule> {
+        let breakpoint_rule = {
+            let mut breakpoint_rules = self.admin_state.breakpoints().rules().write();
+            let index = breakpoint_rules.iter().position(|rule| rule.id == id)?;
+            breakpoint_rules.remove(index)
+        };
+        self.admin_state.breakpoints().resume_unmatched();
+
+        Some(breakpoint_rule)
+    }
+
+    pub async fn clear_breakpoint_rules(&self) -> Vec<BreakpointRule> {
+        let breakpoint_rules = std::mem::take(&mut *self.admin_state.breakpoints().rules().write());
+        self.admin_state.breakpoints().resume_unmatched();
+
+        breakpoint_rules
+    }
+
+    pub async fn resume_paused_message(&self, id: String) -> Option<PausedMessage> {
+        self.admin_state
+            .breakpoints()
+            .resolve(&id, BreakpointResolution::Resume)
+    }
+
+    pub async fn edit_paused_message(
+        &self,
+        id: String,
+        message: serde_json::Value,
+    ) -> Option<PausedMessage> {
+        self.admin_state
+            .breakpoints()
+            .resolve(&id, BreakpointResolution::Edit(message))
+    }
+
+    pub async fn drop_paused_message(&self, id: String) -> Option<PausedMessage> {
+        self.admin_state
+            .breakpoints()
+            .resolve(&id, BreakpointResolution::Drop)
+    }
+
+    pub async fn resume_all_paused_messages(&self) -> Vec<PausedMessage> {
+        self.admin_state.breakpoints().resume_all()
+    }
+
+    pub async fn replay_message(
+        &self,
+        ctx: &Context<'_>,
+        event_id: EventIdScalar,
+        #[graphql(default)] overrides: ReplayOverridesInput,
+    ) -> async_graphql::Result<ReplayedResponse> {
+        let message = self
+            .admin_state
+            .message_sender_ref()
+            .history()
+            .iter()
+            .find(|message| message.event_id == event_id.0)
+            .cloned()
+            .ok_or_else(|| async_graphql::Error::new("The message is not in the history"))?;
+
+        if message.connection_type != ConnectionType::Http
+            || message.message_direction != MessageDirection::Request
+        {
+            return Err(async_graphql::Error::new(
+                "Only HTTP requests can be replayed",
+            ));
+        }
+        if message.message.get("files").is_some() {
+            return Err(async_graphql::Error::new(
+                "Multipart file uploads cannot be replayed",
+            ));
+        }
+
+        let mut graphql_request =
+            serde_json::from_value::<async_graphql::Request>(message.message.as_ref().clone())?;
+        if let Some(variables) = overrides.variables {
+            graphql_request.variables = Variables::from_json(variables);
+        }
+        if let Some(operation_name) = overrides.operation_name {
+            graphql_request.operation_name = Some(operation_name);
+        }
+
+        let mut headers = message
+            .transmitted_headers
+            .as_ref()
+            .map(|headers| {
+                headers
+                    .header_vec()
+                    .iter()
+                    .map(|header| (header.name.0.clone(), header.value.0.clone()))
+                    .collect::<HeaderMap>()
+            })
+            .unwrap_or_default();
+        for header in overrides.headers {
+            headers.insert(header.name.0, header.value.0);
+        }
+
+        let (status, headers, body) = match proxy_graphql_request(
+            ctx.data::<AppState>()?,
+            headers,
+            graphql_request,
+            match message.http_method {
+                Some(HttpMethod::Get) => RequestEncoding::QueryString,
+                Some(HttpMethod::Post) | None => RequestEncoding::Json,
+            },
+            Some(message.event_id),
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err(response) => (
+                StatusCode::OK,
+                HeaderMap::new(),
+                Body::from(serde_json::to_string(&response.0)?),
+            ),
+        };
+
+        let body = tokio::time::timeout(
+            REPLAYED_RESPONSE_TIMEOUT,
+            axum::body::to_bytes(body, REPLAYED_RESPONSE_MAX_SIZE),
+        )
+        .await
+        .map_err(|_| async_graphql::Error::new("The replayed response did not end in time"))?
+        .map_err(|e| {
+            async_graphql::Error::new(format!(
+                "The replayed response cannot be read, error = '{e}'"
+            ))
+        })?;
+        let text = String::from_utf8_lossy(&body);
+
+        Ok(ReplayedResponse {
+            status: status.as_u16(),
+            headers: Headers::from_header_map(headers),
+            response: serde_json::from_str::<serde_json::Value>(&text)
+                .unwrap_or_else(|_| serde_json::Value::from(text.as_ref())),
+        })
+    }
+
+    pub async fn close_connection(
+        &self,
+        id: String,
+        #[graphql(default = 1000)] code: u16,
+        #[graphql(default)] reason: String,
+    ) -> async_graphql::Result<bool> {
+        if !matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999) {
+            return Err(InvalidCloseCodeError { code }.into());
+        }
+
+        Ok(self.admin_state.ws_connections().close(&id, code, reason))
+    }
+
+    pub async fn inject_frame(
+        &self,
+        connection_id: String,
+        message_direction: MessageDirection,
+        frame: serde_json::Value,
+    ) -> bool {
+        let text = match frame {
+            serde_json::Value::String(text) => text,
+            frame => frame.to_string(),
+        };
+
+        self.admin_state
+            .ws_connections()
+            .inject(&connection_id, message_direction, text)
+    }
 }
@@ -1,8 +1,20 @@
-use async_graphql::Object;
+use async_graphql::{
+    connection::{query, Connection, Edge},
+    Object,
+};
 
-use crate::admin_state::AdminState;
+use crate::{admin_state::AdminState, capture::har::create_har, state_snapshot::StateSnapshot};
 
-use super::types::{graphql_endpoints::GraphQLEndpoints, headers::Headers};
+use super::{
+    inputs::message_filter::MessageFilter,
+    types::{
+        active_connection::ActiveConnection, breakpoint_rule::BreakpointRule,
+        connection_init_rule::ConnectionInitRule, fault_rule::FaultRule,
+        graphql_endpoints::GraphQLEndpoints, headers::Headers, message::Message,
+        operation_stub::OperationStub, paused_message::PausedMessage,
+        upstream_profile::UpstreamProfile,
+    },
+};
 
 pub struct Query {
     pub admin_state: AdminState,
@@ -25,4 +37,115 @@ impl Query {
     pub async fn response_headers(&self) -> Headers {
         Headers::from_rw_lock_header_map(self.admin_state.response_headers().clone())
     }
+
+    pub async fn profiles(&self) -> Vec<UpstreamProfile> {
+        self.admin_state
+            .upstream_profiles()
+            .read()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub async fn active_profile(&self) -> Option<String> {
+        self.admin_state.active_upstream_profile().read().clone()
+    }
+
+    pub async fn operation_stubs(&self) -> Vec<OperationStub> {
+        let mut operation_stubs = self
+            .admin_state
+            .operation_stubs()
+            .read()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        operation_stubs.sort_by(|a, b| a.operation_name.cmp(&b.operation_name));
+
+        operation_stubs
+    }
+
+    pub async fn fault_rules(&self) -> Vec<FaultRule> {
+        self.admin_state.fault_rules().read().clone()
+    }
+
+    pub async fn connection_init_rules(&self) -> Vec<ConnectionInitRule> {
+        self.admin_state.connection_init_rules().read().clone()
+    }
+
+    pub async fn breakpoint_rules(&self) -> Vec<BreakpointRule> {
+        self.admin_state.breakpoints().rules().read().clone()
+    }
+
+    pub async fn paused_messages(&self) -> Vec<PausedMessage> {
+        self.admin_state.breakpoints().paused_messages()
+    }
+
+    pub async fn active_connections(&self) -> Vec<ActiveConnection> {
+        self.admin_state.ws_connections().active_connections()
+    }
+
+    pub async fn state_snapshot(&self) -> async_graphql::Result<serde_json::Value> {
+        Ok(serde_json::to_value(StateSnapshot::capture(
+            &self.admin_state,
+        ))?)
+    }
+
+    pub async fn har(
+        &self,
+        #[graphql(default)] message_filters: Vec<MessageFilter>,
+    ) -> serde_json::Value {
+        let messages = self
+            .admin_state
+            .message_sender_ref()
+            .history()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        create_har(&messages, &message_filters)
+    }
+
+    pub async fn messages(
+        &self,
+        #[graphql(default)] message_filters: Vec<MessageFilter>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<Connection<u64, Message>> {
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<u64>, _before: Option<u64>, first, _last| async move {
+                let history = self.admin_state.message_sender_ref().history();
+
+                let mut messages = history.iter_after(after).filter(|message| {
+                    MessageFilter::is_message_allowed_by_filters(&message_filters, message)
+                });
+
+                let edges = messages
+                    .by_ref()
+                    .take(first.unwrap_or(usize::MAX))
+                    .map(|message| Edge::new(message.event_id, message.clone()))
+                    .collect::<Vec<_>>();
+
+                let has_next_page = messages.next().is_some();
+                let has_previous_page = after.is_some_and(|after| {
+                    history.iter().any(|message| {
+                        message.event_id <= after
+                            && MessageFilter::is_message_allowed_by_filters(
+                                &message_filters,
+                                message,
+                            )
+                    })
+                });
+
+                let mut connection = Connection::new(has_previous_page, has_next_page);
+                connection.edges.extend(edges);
+
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
 }
@@ -0,0 +1,31 @@
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+
+#[derive(Debug, Clone, Copy)]
+pub struct EventIdScalar(pub u64);
+
+impl From<u64> for EventIdScalar {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+#[Scalar(name = "EventId")]
+impl ScalarType for EventIdScalar {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            Value::Number(val) => Ok(val
+                .as_u64()
+                .ok_or_else(|| InputValueError::expected_type(value.clone()))?
+                .into()),
+            Value::String(val) => Ok(val
+                .parse::<u64>()
+                .map_err(|_e| InputValueError::expected_type(value.clone()))?
+                .into()),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Number(self.0.into())
+    }
+}
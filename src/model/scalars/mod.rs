@@ -1,2 +1,3 @@
+pub mod event_id_scalar;
 pub mod header_name_scalar;
 pub mod header_value_scalar;
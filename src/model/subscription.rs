@@ -4,7 +4,13 @@ use tokio::sync::broadcast;
 
 use crate::admin_state::AdminState;
 
-use super::{inputs::message_filter::MessageFilter, types::message::Message};
+use super::{
+    inputs::message_filter::MessageFilter,
+    scalars::event_id_scalar::EventIdScalar,
+    types::{
+        config_changed_event::ConfigChangedEvent, message::Message, paused_message::PausedMessage,
+    },
+};
 
 pub struct Subscription {
     pub admin_state: AdminState,
@@ -15,30 +21,89 @@ impl Subscription {
     pub async fn messages(
         &self,
         #[graphql(default)] message_filters: Vec<MessageFilter>,
+        since: Option<EventIdScalar>,
     ) -> impl Stream<Item = Result<Message, broadcast::error::RecvError>> {
-        let mut receiver = self.admin_state.message_receiver();
+        let message_sender = self.admin_state.message_sender_ref().clone();
+
+        // subscribing while the history is locked guarantees that no message is lost between
+        // the replayed and the live messages
+        let (mut receiver, mut next_event_id) = {
+            let history = message_sender.history();
+            let next_event_id = since
+                .map(|since| since.0.saturating_add(1).min(history.next_event_id()))
+                .unwrap_or(history.next_event_id());
+
+            (message_sender.subscribe(), next_event_id)
+        };
+
+        let mut catch_up_from_history = since.is_some();
 
         async_stream::stream! {
             loop {
+                if catch_up_from_history {
+                    catch_up_from_history = false;
+
+                    let (messages, skipped) = message_sender.history().messages_from(next_event_id);
+                    if skipped != 0 {
+                        yield Err(broadcast::error::RecvError::Lagged(skipped));
+                    }
+
+                    for message in messages {
+                        next_event_id = message.event_id + 1;
+                        if MessageFilter::is_message_allowed_by_filters(&message_filters, &message) {
+                            yield Ok(message);
+                        }
+                    }
+
+                    continue;
+                }
+
                 match receiver.recv().await {
                     Ok(message) => {
-                        let mut is_message_allowed = true;
-                        for filter in message_filters.iter() {
-                            if let Some(allowed) = filter.is_message_allowed(&message) {
-                                is_message_allowed = allowed;
-                            }
+                        if message.event_id < next_event_id {
+                            continue;
                         }
 
-                        if is_message_allowed {
+                        next_event_id = message.event_id + 1;
+                        if MessageFilter::is_message_allowed_by_filters(&message_filters, &message) {
                             yield Ok(message);
                         }
                     }
                     Err(broadcast::error::RecvError::Closed) => break,
-                    Err(e @ broadcast::error::RecvError::Lagged(_skipped)) => {
-                        yield Err(e)
+                    Err(broadcast::error::RecvError::Lagged(_skipped)) => {
+                        catch_up_from_history = true;
                     }
                 }
             }
         }
     }
+
+    pub async fn paused_messages(&self) -> impl Stream<Item = Vec<PausedMessage>> {
+        let mut receiver = self.admin_state.breakpoints().subscribe();
+
+        async_stream::stream! {
+            loop {
+                let paused_messages = receiver.borrow_and_update().clone();
+                yield paused_messages;
+
+                if receiver.changed().await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    pub async fn config_changed(&self) -> impl Stream<Item = ConfigChangedEvent> {
+        let mut receiver = self.admin_state.config_changed_sender().subscribe();
+
+        async_stream::stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => yield event,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_skipped)) => (),
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,12 @@
+use async_graphql::SimpleObject;
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ActiveConnection {
+    pub connection_id: String,
+    pub client_address: Option<String>,
+    pub server_endpoint_url: String,
+    pub opened_at: String,
+    pub client_message_count: u64,
+    pub server_message_count: u64,
+    pub active_subscription_ids: Vec<String>,
+}
@@ -0,0 +1,102 @@
+use async_graphql::SimpleObject;
+
+use crate::model::{
+    enums::{
+        connection_type::ConnectionType, message_direction::MessageDirection,
+        operation_type::GraphQLOperationType,
+    },
+    inputs::breakpoint_rule_input::BreakpointRuleInput,
+};
+
+#[derive(Debug, Clone, SimpleObject, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakpointRule {
+    pub id: String,
+    pub connection_type: Option<ConnectionType>,
+    pub message_direction: Option<MessageDirection>,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+}
+
+impl BreakpointRule {
+    pub fn new(input: BreakpointRuleInput) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().as_hyphenated().to_string(),
+            connection_type: input.connection_type,
+            message_direction: input.message_direction,
+            operation_name: input.operation_name,
+            operation_type: input.operation_type,
+        }
+    }
+
+    pub fn is_matching(
+        &self,
+        connection_type: ConnectionType,
+        message_direction: MessageDirection,
+        operation_name: Option<&str>,
+        operation_type: Option<GraphQLOperationType>,
+    ) -> bool {
+        self.connection_type
+            .is_none_or(|rule_connection_type| rule_connection_type == connection_type)
+            && self
+                .message_direction
+                .is_none_or(|rule_message_direction| rule_message_direction == message_direction)
+            && self
+                .operation_name
+                .as_ref()
+                .is_none_or(|rule_operation_name| {
+                    Some(rule_operation_name.as_str()) == operation_name
+                })
+            && self
+                .operation_type
+                .is_none_or(|rule_operation_type| Some(rule_operation_type) == operation_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakpoint_rule_matching() {
+        let rule = BreakpointRule::new(BreakpointRuleInput {
+            connection_type: None,
+            message_direction: None,
+            operation_name: None,
+            operation_type: None,
+        });
+        assert!(rule.is_matching(ConnectionType::Ws, MessageDirection::Request, None, None));
+
+        let rule = BreakpointRule::new(BreakpointRuleInput {
+            connection_type: Some(ConnectionType::Http),
+            message_direction: Some(MessageDirection::Response),
+            operation_name: Some("Foo".to_string()),
+            operation_type: Some(GraphQLOperationType::Query),
+        });
+        assert!(rule.is_matching(
+            ConnectionType::Http,
+            MessageDirection::Response,
+            Some("Foo"),
+            Some(GraphQLOperationType::Query)
+        ));
+        assert!(!rule.is_matching(
+            ConnectionType::Ws,
+            MessageDirection::Response,
+            Some("Foo"),
+            Some(GraphQLOperationType::Query)
+        ));
+        assert!(!rule.is_matching(
+            ConnectionType::Http,
+            MessageDirection::Request,
+            Some("Foo"),
+            Some(GraphQLOperationType::Query)
+        ));
+        assert!(!rule.is_matching(
+            ConnectionType::Http,
+            MessageDirection::Response,
+            Some("Bar"),
+            Some(GraphQLOperationType::Query)
+        ));
+        assert!(!rule.is_matching(ConnectionType::Http, MessageDirection::Response, None, None));
+    }
+}
@@ -0,0 +1,11 @@
+use async_graphql::SimpleObject;
+
+use super::graphql_endpoints::GraphQLEndpoints;
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct ConfigChangedEvent {
+    pub path: String,
+    pub server_endpoints: GraphQLEndpoints,
+    pub prohibit_mutation: bool,
+    pub timestamp: String,
+}
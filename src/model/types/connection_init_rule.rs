@@ -0,0 +1,96 @@
+use async_graphql::SimpleObject;
+
+use crate::model::enums::connection_init_rule_mode::ConnectionInitRuleMode;
+
+#[derive(Debug, Clone, SimpleObject, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInitRule {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub mode: ConnectionInitRuleMode,
+}
+
+impl ConnectionInitRule {
+    // merging keeps the values sent by the client and only adds the missing ones, also inside
+    // nested objects, while overriding replaces the value of the key
+    pub fn apply(&self, payload: &mut serde_json::Map<String, serde_json::Value>) {
+        match self.mode {
+            ConnectionInitRuleMode::Merge => match payload.get_mut(&self.key) {
+                Some(current_value) => merge_json(current_value, &self.value),
+                None => {
+                    payload.insert(self.key.clone(), self.value.clone());
+                }
+            },
+            ConnectionInitRuleMode::Override => {
+                payload.insert(self.key.clone(), self.value.clone());
+            }
+        }
+    }
+
+    pub fn apply_all(
+        rules: &[ConnectionInitRule],
+        payload: &mut serde_json::Map<String, serde_json::Value>,
+    ) {
+        for rule in rules {
+            rule.apply(payload);
+        }
+    }
+}
+
+fn merge_json(current_value: &mut serde_json::Value, value: &serde_json::Value) {
+    if let (serde_json::Value::Object(current_map), serde_json::Value::Object(map)) =
+        (current_value, value)
+    {
+        for (key, value) in map {
+            match current_map.get_mut(key) {
+                Some(current_value) => merge_json(current_value, value),
+                None => {
+                    current_map.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_init_rules() {
+        let rules = [
+            ConnectionInitRule {
+                key: "authorization".to_string(),
+                value: serde_json::json!("Bearer foo"),
+                mode: ConnectionInitRuleMode::Override,
+            },
+            ConnectionInitRule {
+                key: "headers".to_string(),
+                value: serde_json::json!({ "x-foo": "foo", "x-bar": "bar" }),
+                mode: ConnectionInitRuleMode::Merge,
+            },
+            ConnectionInitRule {
+                key: "locale".to_string(),
+                value: serde_json::json!("en"),
+                mode: ConnectionInitRuleMode::Merge,
+            },
+        ];
+
+        let serde_json::Value::Object(mut payload) = serde_json::json!({
+            "authorization": "Bearer bar",
+            "headers": { "x-foo": "client" },
+        }) else {
+            unreachable!();
+        };
+        ConnectionInitRule::apply_all(&rules, &mut payload);
+
+        assert_eq!(
+            serde_json::Value::Object(payload),
+            serde_json::json!({
+                "authorization": "Bearer foo",
+                "headers": { "x-foo": "client", "x-bar": "bar" },
+                "locale": "en",
+            })
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use async_graphql::SimpleObject;
+
+use crate::{
+    model::{
+        enums::{connection_type::ConnectionType, operation_type::GraphQLOperationType},
+        inputs::fault_rule_input::FaultRuleInput,
+    },
+    utils::random_u64,
+};
+
+#[derive(Debug, Clone, SimpleObject, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultRule {
+    pub id: String,
+    pub connection_type: Option<ConnectionType>,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+    pub percentage: f64,
+    pub latency_ms: Option<u64>,
+    pub error_message: Option<String>,
+    pub status: Option<u16>,
+    pub truncate_body_bytes: Option<u64>,
+    pub close_after_messages: Option<u64>,
+}
+
+impl FaultRule {
+    pub fn new(input: FaultRuleInput) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().as_hyphenated().to_string(),
+            connection_type: input.connection_type,
+            operation_name: input.operation_name,
+            operation_type: input.operation_type,
+            percentage: input.percentage,
+            latency_ms: input.latency_ms,
+            error_message: input.error_message,
+            status: input.status,
+            truncate_body_bytes: input.truncate_body_bytes,
+            close_after_messages: input.close_after_messages,
+        }
+    }
+
+    pub fn is_matching_connection(&self, connection_type: ConnectionType) -> bool {
+        self.connection_type
+            .is_none_or(|rule_connection_type| rule_connection_type == connection_type)
+    }
+
+    pub fn is_matching_operation(
+        &self,
+        operation_name: Option<&str>,
+        operation_type: Option<GraphQLOperationType>,
+    ) -> bool {
+        if let Some(rule_operation_name) = &self.operation_name {
+            if operation_name != Some(rule_operation_name.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(rule_operation_type) = self.operation_type {
+            if operation_type != Some(rule_operation_type) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn is_selected_by_percentage(&self) -> bool {
+        ((random_u64() % 10_000) as f64 / 100.0) < self.percentage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_fault_rule(
+        operation_name: Option<&str>,
+        operation_type: Option<GraphQLOperationType>,
+        percentage: f64,
+    ) -> FaultRule {
+        FaultRule::new(FaultRuleInput {
+            connection_type: Some(ConnectionType::Http),
+            operation_name: operation_name.map(|operation_name| operation_name.to_string()),
+            operation_type,
+            percentage,
+            latency_ms: None,
+            error_message: None,
+            status: None,
+            truncate_body_bytes: None,
+            close_after_messages: None,
+        })
+    }
+
+    #[test]
+    fn test_fault_rule_matching() {
+        let rule = create_fault_rule(None, None, 100.0);
+        assert!(rule.is_matching_connection(ConnectionType::Http));
+        assert!(!rule.is_matching_connection(ConnectionType::Ws));
+        assert!(rule.is_matching_operation(None, None));
+
+        let rule = create_fault_rule(Some("Foo"), Some(GraphQLOperationType::Query), 100.0);
+        assert!(rule.is_matching_operation(Some("Foo"), Some(GraphQLOperationType::Query)));
+        assert!(!rule.is_matching_operation(Some("Bar"), Some(GraphQLOperationType::Query)));
+        assert!(!rule.is_matching_operation(Some("Foo"), Some(GraphQLOperationType::Mutation)));
+        assert!(!rule.is_matching_operation(None, None));
+    }
+
+    #[test]
+    fn test_fault_rule_percentage() {
+        assert!((0..100).all(|_| create_fault_rule(None, None, 100.0).is_selected_by_percentage()));
+        assert!((0..100).all(|_| !create_fault_rule(None, None, 0.0).is_selected_by_percentage()));
+    }
+}
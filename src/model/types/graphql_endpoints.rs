@@ -1,8 +1,10 @@
 use async_graphql::Object;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct GraphQLEndpoints {
+    #[serde(rename = "graphQlEndpoint")]
     pub graphql_endpoint: String,
+    #[serde(rename = "graphQlWsEndpoint")]
     pub graphql_ws_endpoint: String,
 }
 
@@ -41,6 +41,19 @@ impl Headers {
         }
     }
 
+    pub fn header_vec(&self) -> &Vec<Header> {
+        match self {
+            Headers::RwLockHeaderMap {
+                header_map: _header_map,
+                header_vec,
+            } => header_vec,
+            Headers::HeaderMap {
+                header_map: _header_map,
+                header_vec,
+            } => header_vec,
+        }
+    }
+
     fn collect_headers_to_vec(header_map: &HeaderMap) -> Vec<Header> {
         header_map
             .iter()
@@ -73,15 +86,6 @@ impl Headers {
     }
 
     pub async fn all(&self) -> &Vec<Header> {
-        match self {
-            Headers::RwLockHeaderMap {
-                header_map: _header_map,
-                header_vec,
-            } => &header_vec,
-            Headers::HeaderMap {
-                header_map: _header_map,
-                header_vec,
-            } => &header_vec,
-        }
+        self.header_vec()
     }
 }
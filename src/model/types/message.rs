@@ -1,13 +1,23 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use async_graphql::Object;
 
-use crate::model::enums::{connection_type::ConnectionType, message_direction::MessageDirection};
+use crate::model::{
+    enums::{
+        connection_type::ConnectionType, http_method::HttpMethod,
+        message_direction::MessageDirection, message_origin::MessageOrigin,
+    },
+    scalars::event_id_scalar::EventIdScalar,
+};
 
 use super::headers::Headers;
 
 #[derive(Clone)]
 pub struct Message {
+    pub event_id: u64,
     pub sequence_counter: u64,
     pub message: Arc<serde_json::Value>,
     pub connection_type: ConnectionType,
@@ -15,10 +25,20 @@ pub struct Message {
     pub connection_id: Arc<String>,
     pub transmitted_headers: Option<Arc<Headers>>,
     pub server_endpoint_url: Arc<String>,
+    pub timestamp: SystemTime,
+    pub latency: Option<Duration>,
+    pub origin: MessageOrigin,
+    pub replayed_event_id: Option<u64>,
+    pub status_code: Option<u16>,
+    pub http_method: Option<HttpMethod>,
 }
 
 #[Object]
 impl Message {
+    async fn event_id(&self) -> EventIdScalar {
+        self.event_id.into()
+    }
+
     async fn sequence_counter(&self) -> u64 {
         self.sequence_counter
     }
@@ -46,4 +66,51 @@ impl Message {
     async fn server_endpoint_url(&self) -> &String {
         &self.server_endpoint_url
     }
+
+    async fn timestamp(&self) -> String {
+        humantime::format_rfc3339_micros(self.timestamp).to_string()
+    }
+
+    async fn latency_ms(&self) -> Option<f64> {
+        self.latency.map(|latency| latency.as_secs_f64() * 1000.0)
+    }
+
+    async fn origin(&self) -> MessageOrigin {
+        self.origin
+    }
+
+    async fn replayed_event_id(&self) -> Option<EventIdScalar> {
+        self.replayed_event_id
+            .map(|replayed_event_id| replayed_event_id.into())
+    }
+
+    async fn status_code(&self) -> Option<u16> {
+        self.status_code
+    }
+
+    async fn http_method(&self) -> Option<HttpMethod> {
+        self.http_method
+    }
+}
+
+#[cfg(test)]
+impl Message {
+    pub fn for_test() -> Self {
+        Self {
+            event_id: 0,
+            sequence_counter: 0,
+            message: Arc::new(serde_json::Value::Null),
+            connection_type: ConnectionType::Http,
+            message_direction: MessageDirection::Request,
+            connection_id: Arc::new("connection".to_string()),
+            transmitted_headers: None,
+            server_endpoint_url: Arc::new("http://localhost/api/graphql".to_string()),
+            timestamp: SystemTime::UNIX_EPOCH,
+            latency: None,
+            origin: MessageOrigin::Client,
+            replayed_event_id: None,
+            status_code: None,
+            http_method: None,
+        }
+    }
 }
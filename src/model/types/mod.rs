@@ -1,4 +1,13 @@
+pub mod active_connection;
+pub mod breakpoint_rule;
+pub mod config_changed_event;
+pub mod connection_init_rule;
+pub mod fault_rule;
 pub mod graphql_endpoints;
 pub mod header;
 pub mod headers;
 pub mod message;
+pub mod operation_stub;
+pub mod paused_message;
+pub mod replayed_response;
+pub mod upstream_profile;
@@ -0,0 +1,10 @@
+use async_graphql::SimpleObject;
+
+#[derive(Debug, Clone, SimpleObject, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationStub {
+    pub operation_name: String,
+    pub response: serde_json::Value,
+    pub status: u16,
+    pub delay_ms: u64,
+}
@@ -0,0 +1,18 @@
+use async_graphql::SimpleObject;
+
+use crate::model::enums::{
+    connection_type::ConnectionType, message_direction::MessageDirection,
+    operation_type::GraphQLOperationType,
+};
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct PausedMessage {
+    pub id: String,
+    pub connection_id: String,
+    pub connection_type: ConnectionType,
+    pub message_direction: MessageDirection,
+    pub operation_name: Option<String>,
+    pub operation_type: Option<GraphQLOperationType>,
+    pub message: serde_json::Value,
+    pub timestamp: String,
+}
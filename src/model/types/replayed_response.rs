@@ -0,0 +1,10 @@
+use async_graphql::SimpleObject;
+
+use super::headers::Headers;
+
+#[derive(Debug, SimpleObject)]
+pub struct ReplayedResponse {
+    pub status: u16,
+    pub headers: Headers,
+    pub response: serde_json::Value,
+}
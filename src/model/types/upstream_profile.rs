@@ -0,0 +1,52 @@
+use async_graphql::Object;
+use http::HeaderMap;
+
+use crate::model::inputs::upstream_profile_input::UpstreamProfileInput;
+
+use super::{graphql_endpoints::GraphQLEndpoints, headers::Headers};
+
+#[derive(Debug, Clone)]
+pub struct UpstreamProfile {
+    pub name: String,
+    pub server_endpoints: GraphQLEndpoints,
+    pub request_headers: HeaderMap,
+    pub accept_invalid_certificates: bool,
+}
+
+impl UpstreamProfile {
+    pub fn new(input: UpstreamProfileInput) -> Self {
+        let mut request_headers = HeaderMap::new();
+        for header in input.request_headers {
+            request_headers.append(header.name.as_header_name().clone(), header.value.0);
+        }
+
+        Self {
+            name: input.name,
+            server_endpoints: GraphQLEndpoints {
+                graphql_endpoint: input.graphql_endpoint,
+                graphql_ws_endpoint: input.graphql_ws_endpoint,
+            },
+            request_headers,
+            accept_invalid_certificates: input.accept_invalid_certificates,
+        }
+    }
+}
+
+#[Object]
+impl UpstreamProfile {
+    async fn name(&self) -> &String {
+        &self.name
+    }
+
+    async fn server_endpoints(&self) -> &GraphQLEndpoints {
+        &self.server_endpoints
+    }
+
+    async fn request_headers(&self) -> Headers {
+        Headers::from_header_map(self.request_headers.clone())
+    }
+
+    async fn accept_invalid_certificates(&self) -> bool {
+        self.accept_invalid_certificates
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, HashMap};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::{
+    admin_state::AdminState,
+    error::{
+        CannotParseStateSnapshotError, InvalidFaultRulePercentageError,
+        InvalidStateSnapshotHeaderError, RestoreStateSnapshotError, UnknownUpstreamProfileError,
+        UnsupportedStateSnapshotVersionError,
+    },
+    model::types::{
+        breakpoint_rule::BreakpointRule, connection_init_rule::ConnectionInitRule,
+        fault_rule::FaultRule, graphql_endpoints::GraphQLEndpoints, operation_stub::OperationStub,
+        upstream_profile::UpstreamProfile,
+    },
+};
+
+const STATE_SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StateSnapshotHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshotUpstreamProfile {
+    pub name: String,
+    pub server_endpoints: GraphQLEndpoints,
+    pub request_headers: Vec<StateSnapshotHeader>,
+    pub accept_invalid_certificates: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    pub version: u32,
+    pub server_endpoints: GraphQLEndpoints,
+    pub prohibit_mutation: bool,
+    #[serde(default)]
+    pub accept_invalid_certificates: bool,
+    pub request_headers: Vec<StateSnapshotHeader>,
+    pub response_headers: Vec<StateSnapshotHeader>,
+    pub operation_stubs: Vec<OperationStub>,
+    pub fault_rules: Vec<FaultRule>,
+    pub connection_init_rules: Vec<ConnectionInitRule>,
+    pub breakpoint_rules: Vec<BreakpointRule>,
+    #[serde(default)]
+    pub upstream_profiles: Vec<StateSnapshotUpstreamProfile>,
+    #[serde(default)]
+    pub active_upstream_profile: Option<String>,
+}
+
+impl StateSnapshot {
+    pub fn capture(admin_state: &AdminState) -> Self {
+        let mut operation_stubs = admin_state
+            .operation_stubs()
+            .read()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        operation_stubs.sort_by(|a, b| a.operation_name.cmp(&b.operation_name));
+
+        let upstream_settings = admin_state.upstream_settings();
+
+        Self {
+            version: STATE_SNAPSHOT_VERSION,
+            server_endpoints: upstream_settings.server_endpoints,
+            prohibit_mutation: admin_state.prohibit_mutation(),
+            accept_invalid_certificates: upstream_settings.accept_invalid_certificates,
+            request_headers: snapshot_headers(&upstream_settings.request_headers),
+            response_headers: snapshot_headers(&admin_state.response_headers().read()),
+            operation_stubs,
+            fault_rules: admin_state.fault_rules().read().clone(),
+            connection_init_rules: admin_state.connection_init_rules().read().clone(),
+            breakpoint_rules: admin_state.breakpoints().rules().read().clone(),
+            upstream_profiles: admin_state
+                .upstream_profiles()
+                .read()
+                .values()
+                .map(|upstream_profile| StateSnapshotUpstreamProfile {
+                    name: upstream_profile.name.clone(),
+                    server_endpoints: upstream_profile.server_endpoints.clone(),
+                    request_headers: snapshot_headers(&upstream_profile.request_headers),
+                    accept_invalid_certificates: upstream_profile.accept_invalid_certificates,
+                })
+                .collect(),
+            active_upstream_profile: admin_state.active_upstream_profile().read().clone(),
+        }
+    }
+
+    pub fn from_json(snapshot: serde_json::Value) -> Result<Self, RestoreStateSnapshotError> {
+        let snapshot = serde_json::from_value::<Self>(snapshot)
+            .map_err(|e| CannotParseStateSnapshotError { source: e })?;
+        if snapshot.version != STATE_SNAPSHOT_VERSION {
+            return Err(UnsupportedStateSnapshotVersionError {
+                version: snapshot.version,
+            }
+            .into());
+        }
+
+        Ok(snapshot)
+    }
+
+    // the whole admin state is replaced, nothing is changed when the snapshot is invalid
+    pub fn restore(self, admin_state: &AdminState) -> Result<(), RestoreStateSnapshotError> {
+        let request_headers = header_map(self.request_headers)?;
+        let response_headers = header_map(self.response_headers)?;
+        let upstream_profiles = self
+            .upstream_profiles
+            .into_iter()
+            .map(|upstream_profile| {
+                Ok((
+                    upstream_profile.name.clone(),
+                    UpstreamProfile {
+                        name: upstream_profile.name,
+                        server_endpoints: upstream_profile.server_endpoints,
+                        request_headers: header_map(upstream_profile.request_headers)?,
+                        accept_invalid_certificates: upstream_profile.accept_invalid_certificates,
+                    },
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>, InvalidStateSnapshotHeaderError>>()?;
+        if let Some(fault_rule) = self
+            .fault_rules
+            .iter()
+            .find(|fault_rule| !(0.0..=100.0).contains(&fault_rule.percentage))
+        {
+            return Err(InvalidFaultRulePercentageError {
+                percentage: fault_rule.percentage,
+            }
+            .into());
+        }
+        if let Some(name) = self
+            .active_upstream_profile
+            .as_ref()
+            .filter(|name| !upstream_profiles.contains_key(*name))
+        {
+            return Err(UnknownUpstreamProfileError { name: name.clone() }.into());
+        }
+
+        admin_state.update_upstream_settings(|server_endpoints, upstream_request_headers| {
+            *server_endpoints = self.server_endpoints;
+            *upstream_request_headers = request_headers;
+            admin_state.set_accept_invalid_certificates(self.accept_invalid_certificates);
+            *admin_state.active_upstream_profile().write() = self.active_upstream_profile;
+        });
+        admin_state.set_prohibit_mutation(self.prohibit_mutation);
+        *admin_state.response_headers().write() = response_headers;
+        *admin_state.operation_stubs().write() = self
+            .operation_stubs
+            .into_iter()
+            .map(|operation_stub| (operation_stub.operation_name.clone(), operation_stub))
+            .collect::<HashMap<_, _>>();
+        *admin_state.fault_rules().write() = self.fault_rules;
+        *admin_state.connection_init_rules().write() = self.connection_init_rules;
+        *admin_state.breakpoints().rules().write() = self.breakpoint_rules;
+        admin_state.breakpoints().resume_unmatched();
+        *admin_state.upstream_profiles().write() = upstream_profiles;
+
+        Ok(())
+    }
+}
+
+fn snapshot_headers(headers: &HeaderMap) -> Vec<StateSnapshotHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| StateSnapshotHeader {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn header_map(
+    headers: Vec<StateSnapshotHeader>,
+) -> Result<HeaderMap, InvalidStateSnapshotHeaderError> {
+    let mut header_map = HeaderMap::new();
+    for header in headers {
+        match (
+            HeaderName::try_from(&header.name),
+            HeaderValue::try_from(&header.value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                header_map.append(name, value);
+            }
+            _ => return Err(InvalidStateSnapshotHeaderError { name: header.name }),
+        }
+    }
+
+    Ok(header_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        enums::connection_init_rule_mode::ConnectionInitRuleMode,
+        inputs::breakpoint_rule_input::BreakpointRuleInput,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_state_snapshot_round_trip() {
+        let admin_state = AdminState::new(
+            "http://localhost:8001/api/graphql",
+            "ws://localhost:8001/api/graphql-ws",
+            true,
+            HeaderMap::from_iter([(
+                HeaderName::from_static("authorization"),
+                HeaderValue::from_static("Bearer foo"),
+            )]),
+            HeaderMap::new(),
+            0,
+        );
+        admin_state.operation_stubs().write().insert(
+            "Foo".to_string(),
+            OperationStub {
+                operation_name: "Foo".to_string(),
+                response: serde_json::json!({ "data": { "foo": 1 } }),
+                status: 200,
+                delay_ms: 0,
+            },
+        );
+        admin_state
+            .connection_init_rules()
+            .write()
+            .push(ConnectionInitRule {
+                key: "locale".to_string(),
+                value: serde_json::json!("en"),
+                mode: ConnectionInitRuleMode::Merge,
+            });
+        admin_state
+            .breakpoints()
+            .rules()
+            .write()
+            .push(BreakpointRule::new(BreakpointRuleInput {
+                connection_type: None,
+                message_direction: None,
+                operation_name: Some("Foo".to_string()),
+                operation_type: None,
+            }));
+
+        let snapshot = serde_json::json!(StateSnapshot::capture(&admin_state));
+        assert_eq!(
+            snapshot["serverEndpoints"]["graphQlEndpoint"],
+            "http://localhost:8001/api/graphql"
+        );
+        assert_eq!(
+            snapshot["requestHeaders"],
+            serde_json::json!([{ "name": "authorization", "value": "Bearer foo" }])
+        );
+        assert_eq!(snapshot["connectionInitRules"][0]["mode"], "MERGE");
+
+        let restored_admin_state =
+            AdminState::new("", "", false, HeaderMap::new(), HeaderMap::new(), 0);
+        StateSnapshot::from_json(snapshot.clone())
+            .unwrap()
+            .restore(&restored_admin_state)
+            .unwrap();
+
+        assert!(restored_admin_state.prohibit_mutation());
+        assert_eq!(
+            serde_json::json!(StateSnapshot::capture(&restored_admin_state)),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn test_invalid_state_snapshot() {
+        let admin_state = AdminState::new("", "", false, HeaderMap::new(), HeaderMap::new(), 0);
+        let mut snapshot = serde_json::json!(StateSnapshot::capture(&admin_state));
+
+        snapshot["version"] = serde_json::json!(2);
+        assert!(matches!(
+            StateSnapshot::from_json(snapshot.clone()),
+            Err(RestoreStateSnapshotError::UnsupportedVersion(_))
+        ));
+
+        snapshot["version"] = serde_json::json!(STATE_SNAPSHOT_VERSION);
+        snapshot["requestHeaders"] = serde_json::json!([{ "name": "x foo", "value": "bar" }]);
+        assert!(matches!(
+            StateSnapshot::from_json(snapshot)
+                .unwrap()
+                .restore(&admin_state),
+            Err(RestoreStateSnapshotError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_state_snapshot_settings() {
+        let admin_state = AdminState::new("", "", true, HeaderMap::new(), HeaderMap::new(), 0);
+        let snapshot = StateSnapshot::capture(&admin_state);
+
+        for percentage in [150.0, f64::NAN] {
+            let fault_rule = FaultRule {
+                id: "foo".to_string(),
+                connection_type: None,
+                operation_name: None,
+                operation_type: None,
+                percentage,
+                latency_ms: None,
+                error_message: None,
+                status: None,
+                truncate_body_bytes: None,
+                close_after_messages: None,
+            };
+            assert!(matches!(
+                StateSnapshot {
+                    prohibit_mutation: false,
+                    fault_rules: vec![fault_rule],
+                    ..snapshot.clone()
+                }
+                .restore(&admin_state),
+                Err(RestoreStateSnapshotError::InvalidFaultRulePercentage(_))
+            ));
+        }
+
+        assert!(matches!(
+            StateSnapshot {
+                prohibit_mutation: false,
+                active_upstream_profile: Some("foo".to_string()),
+                ..snapshot
+            }
+            .restore(&admin_state),
+            Err(RestoreStateSnapshotError::UnknownUpstreamProfile(_))
+        ));
+        assert!(admin_state.prohibit_mutation());
+    }
+}
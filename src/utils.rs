@@ -1,5 +1,24 @@
+use std::{
+    sync::{
+        atomic::{self, AtomicU64},
+        LazyLock,
+    },
+    time::SystemTime,
+};
+
 use axum::http::HeaderMap;
 
+const SPLITMIX64_GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+static RANDOM_STATE: LazyLock<AtomicU64> = LazyLock::new(|| {
+    AtomicU64::new(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64,
+    )
+});
+
 #[macro_export]
 macro_rules! log_location {
     () => {
@@ -21,3 +40,18 @@ pub fn move_and_replace_headers(
         }
     }
 }
+
+pub fn mix_bits(value: u64) -> u64 {
+    let value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+// splitmix64 seeded with the start time of the proxy, it is not suitable for cryptography
+pub fn random_u64() -> u64 {
+    mix_bits(
+        RANDOM_STATE
+            .fetch_add(SPLITMIX64_GAMMA, atomic::Ordering::Relaxed)
+            .wrapping_add(SPLITMIX64_GAMMA),
+    )
+}
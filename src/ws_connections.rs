@@ -0,0 +1,343 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use axum::extract::ws::{CloseFrame as AxumCloseFrame, Message as AxumWsMessage};
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+    endpoints::graphql_ws_proxy::WsMessageCapture,
+    model::{
+        enums::{message_direction::MessageDirection, message_origin::MessageOrigin},
+        types::active_connection::ActiveConnection,
+    },
+};
+
+// the senders are weak, so the registry does not keep the channels of a finished connection open
+pub struct WsChannels {
+    server_to_client_sender: mpsc::WeakUnboundedSender<AxumWsMessage>,
+    client_to_server_sender: mpsc::WeakUnboundedSender<AxumWsMessage>,
+}
+
+impl WsChannels {
+    pub fn new(
+        server_to_client_sender: &mpsc::UnboundedSender<AxumWsMessage>,
+        client_to_server_sender: &mpsc::UnboundedSender<AxumWsMessage>,
+    ) -> Self {
+        Self {
+            server_to_client_sender: server_to_client_sender.downgrade(),
+            client_to_server_sender: client_to_server_sender.downgrade(),
+        }
+    }
+}
+
+fn send_to_channel(
+    sender: &mpsc::WeakUnboundedSender<AxumWsMessage>,
+    message: AxumWsMessage,
+) -> bool {
+    sender
+        .upgrade()
+        .is_some_and(|sender| sender.send(message).is_ok())
+}
+
+pub struct WsConnection {
+    connection_id: Arc<String>,
+    client_address: Option<String>,
+    server_endpoint_url: Arc<String>,
+    opened_at: SystemTime,
+    client_message_count: AtomicU64,
+    server_message_count: AtomicU64,
+    active_operation_ids: Mutex<Vec<String>>,
+    close_sender: watch::Sender<Option<AxumCloseFrame<'static>>>,
+    message_capture: WsMessageCapture,
+    channels: WsChannels,
+}
+
+impl WsConnection {
+    pub fn subscribe_close(&self) -> watch::Receiver<Option<AxumCloseFrame<'static>>> {
+        self.close_sender.subscribe()
+    }
+
+    pub fn close(&self, close_frame: AxumCloseFrame<'static>) {
+        self.close_sender.send_replace(Some(close_frame));
+    }
+
+    // stops the pumps with the close frame that is set, if any
+    pub fn stop(&self) {
+        self.close_sender.send_modify(|_close_frame| ());
+    }
+
+    pub fn track_client_message(&self, message: &AxumWsMessage) {
+        self.client_message_count
+            .fetch_add(1, atomic::Ordering::SeqCst);
+
+        match parse_frame_type_and_id(message) {
+            Some((frame_type, id)) if frame_type == "subscribe" || frame_type == "start" => {
+                let mut active_operation_ids = self.active_operation_ids.lock();
+                if !active_operation_ids.contains(&id) {
+                    active_operation_ids.push(id);
+                }
+            }
+            Some((frame_type, id)) if frame_type == "complete" || frame_type == "stop" => {
+                self.remove_active_operation_id(&id);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn track_server_message(&self, message: &AxumWsMessage) {
+        self.server_message_count
+            .fetch_add(1, atomic::Ordering::SeqCst);
+
+        if let Some((frame_type, id)) = parse_frame_type_and_id(message) {
+            if frame_type == "complete" || frame_type == "error" {
+                self.remove_active_operation_id(&id);
+            }
+        }
+    }
+
+    fn remove_active_operation_id(&self, id: &str) {
+        self.active_operation_ids
+            .lock()
+            .retain(|active_operation_id| active_operation_id != id);
+    }
+
+    fn to_active_connection(&self) -> ActiveConnection {
+        ActiveConnection {
+            connection_id: self.connection_id.to_string(),
+            client_address: self.client_address.clone(),
+            server_endpoint_url: self.server_endpoint_url.to_string(),
+            opened_at: humantime::format_rfc3339_micros(self.opened_at).to_string(),
+            client_message_count: self.client_message_count.load(atomic::Ordering::SeqCst),
+            server_message_count: self.server_message_count.load(atomic::Ordering::SeqCst),
+            active_subscription_ids: self.active_operation_ids.lock().clone(),
+        }
+    }
+}
+
+fn parse_frame_type_and_id(message: &AxumWsMessage) -> Option<(String, String)> {
+    let AxumWsMessage::Text(text) = message else {
+        return None;
+    };
+
+    let frame = serde_json::from_str::<serde_json::Value>(text).ok()?;
+    let frame_type = frame.get("type")?.as_str()?.to_string();
+    let id = match frame.get("id")? {
+        serde_json::Value::String(id) => id.clone(),
+        id => id.to_string(),
+    };
+
+    Some((frame_type, id))
+}
+
+pub struct WsConnectionGuard {
+    connection: Arc<WsConnection>,
+    ws_connections: WsConnections,
+}
+
+impl WsConnectionGuard {
+    pub fn connection(&self) -> &Arc<WsConnection> {
+        &self.connection
+    }
+}
+
+impl Drop for WsConnectionGuard {
+    fn drop(&mut self) {
+        self.ws_connections
+            .0
+            .write()
+            .remove(self.connection.connection_id.as_ref());
+    }
+}
+
+#[derive(Clone)]
+pub struct WsConnections(Arc<RwLock<HashMap<String, Arc<WsConnection>>>>);
+
+impl WsConnections {
+    pub fn new() -> Self {
+        Self(Arc::new(RwLock::new(HashMap::new())))
+    }
+
+    pub fn register(
+        &self,
+        connection_id: Arc<String>,
+        client_address: Option<String>,
+        server_endpoint_url: Arc<String>,
+        message_capture: WsMessageCapture,
+        channels: WsChannels,
+    ) -> WsConnectionGuard {
+        let connection = Arc::new(WsConnection {
+            connection_id: connection_id.clone(),
+            client_address,
+            server_endpoint_url,
+            opened_at: SystemTime::now(),
+            client_message_count: AtomicU64::new(0),
+            server_message_count: AtomicU64::new(0),
+            active_operation_ids: Mutex::new(Vec::new()),
+            close_sender: watch::Sender::new(None),
+            message_capture,
+            channels,
+        });
+
+        self.0
+            .write()
+            .insert(connection_id.to_string(), connection.clone());
+
+        WsConnectionGuard {
+            connection,
+            ws_connections: self.clone(),
+        }
+    }
+
+    pub fn active_connections(&self) -> Vec<ActiveConnection> {
+        let mut connections = self.0.read().values().cloned().collect::<Vec<_>>();
+        connections.sort_by_key(|connection| connection.opened_at);
+
+        connections
+            .iter()
+            .map(|connection| connection.to_active_connection())
+            .collect()
+    }
+
+    pub fn close(&self, connection_id: &str, code: u16, reason: String) -> bool {
+        let Some(connection) = self.0.read().get(connection_id).cloned() else {
+            return false;
+        };
+
+        connection.close(AxumCloseFrame {
+            code,
+            reason: reason.into(),
+        });
+
+        true
+    }
+
+    pub fn inject(
+        &self,
+        connection_id: &str,
+        message_direction: MessageDirection,
+        text: String,
+    ) -> bool {
+        let Some(connection) = self.0.read().get(connection_id).cloned() else {
+            return false;
+        };
+
+        let message = AxumWsMessage::Text(text);
+        connection
+            .message_capture
+            .send_axum_ws_message_to_subscriptions(
+                &message,
+                message_direction,
+                None,
+                MessageOrigin::Injected,
+            );
+
+        match message_direction {
+            MessageDirection::Request => {
+                connection.track_client_message(&message);
+                send_to_channel(&connection.channels.client_to_server_sender, message)
+            }
+            MessageDirection::Response => {
+                connection.track_server_message(&message);
+                send_to_channel(&connection.channels.server_to_client_sender, message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{admin_state::ConnectionId, message_sender::MessageSender};
+
+    use super::*;
+
+    fn create_text_message(frame: serde_json::Value) -> AxumWsMessage {
+        AxumWsMessage::Text(frame.to_string())
+    }
+
+    #[test]
+    fn test_ws_connections() {
+        let ws_connections = WsConnections::new();
+        let server_endpoint_url = Arc::new("ws://localhost/api/graphql-ws".to_string());
+        let (server_to_client_sender, mut server_to_client_receiver) = mpsc::unbounded_channel();
+        let (client_to_server_sender, client_to_server_receiver) = mpsc::unbounded_channel();
+        let guard = ws_connections.register(
+            Arc::new("connection".to_string()),
+            Some("127.0.0.1:1234".to_string()),
+            server_endpoint_url.clone(),
+            WsMessageCapture::new(
+                ConnectionId::new(),
+                MessageSender::new(0),
+                server_endpoint_url,
+            ),
+            WsChannels::new(&server_to_client_sender, &client_to_server_sender),
+        );
+        let connection = guard.connection();
+
+        connection.track_client_message(&create_text_message(
+            serde_json::json!({ "type": "connection_init" }),
+        ));
+        connection.track_client_message(&create_text_message(
+            serde_json::json!({ "id": "1", "type": "subscribe", "payload": {} }),
+        ));
+        connection.track_client_message(&create_text_message(
+            serde_json::json!({ "id": 2, "type": "start", "payload": {} }),
+        ));
+        connection.track_server_message(&create_text_message(
+            serde_json::json!({ "id": "1", "type": "complete" }),
+        ));
+
+        let active_connections = ws_connections.active_connections();
+        assert_eq!(active_connections.len(), 1);
+        assert_eq!(active_connections[0].connection_id, "connection");
+        assert_eq!(
+            active_connections[0].client_address.as_deref(),
+            Some("127.0.0.1:1234")
+        );
+        assert_eq!(active_connections[0].client_message_count, 3);
+        assert_eq!(active_connections[0].server_message_count, 1);
+        assert_eq!(active_connections[0].active_subscription_ids, vec!["2"]);
+
+        let complete_frame = serde_json::json!({ "id": 2, "type": "complete" }).to_string();
+        assert!(ws_connections.inject(
+            "connection",
+            MessageDirection::Response,
+            complete_frame.clone()
+        ));
+        assert!(!ws_connections.inject("unknown", MessageDirection::Request, String::new()));
+        assert_eq!(
+            server_to_client_receiver.try_recv().unwrap(),
+            AxumWsMessage::Text(complete_frame)
+        );
+        assert!(ws_connections.active_connections()[0]
+            .active_subscription_ids
+            .is_empty());
+
+        let close_receiver = connection.subscribe_close();
+        assert!(!ws_connections.close("unknown", 1000, String::new()));
+        assert!(ws_connections.close("connection", 4000, "foo".to_string()));
+        assert_eq!(
+            close_receiver
+                .borrow()
+                .as_ref()
+                .map(|close_frame| (close_frame.code, close_frame.reason.to_string())),
+            Some((4000, "foo".to_string()))
+        );
+
+        drop(client_to_server_sender);
+        drop(client_to_server_receiver);
+        assert!(!ws_connections.inject("connection", MessageDirection::Request, String::new()));
+        drop(server_to_client_sender);
+        assert!(server_to_client_receiver.try_recv().is_err());
+        assert!(server_to_client_receiver.is_closed());
+
+        drop(guard);
+        assert!(ws_connections.active_connections().is_empty());
+    }
+}